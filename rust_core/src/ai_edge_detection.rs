@@ -2,6 +2,12 @@ use pyo3::exceptions::PyRuntimeError;
 use pyo3::prelude::*;
 use image::{GenericImageView, DynamicImage};
 use std::path::PathBuf;
+use ndarray::Array4;
+
+/// Model input size for the edge-detection ONNX model. Models are expected to
+/// emit an edge map at the same resolution, which is then resized back up to
+/// the source image's dimensions.
+const MODEL_INPUT_SIZE: (u32, u32) = (256, 256);
 
 /// AI-enhanced edge detection using ONNX models
 /// This module provides ML-based edge detection that can be blended with traditional methods
@@ -52,7 +58,7 @@ fn postprocess_edges(output: Vec<f32>, _width: u32, _height: u32) -> Vec<u8> {
 }
 
 /// AI-enhanced edge detection with fallback
-/// 
+///
 /// This function attempts to use an ONNX model for edge detection.
 /// If the model is unavailable, it falls back to traditional Sobel edge detection.
 pub fn ai_edge_detection(
@@ -60,27 +66,67 @@ pub fn ai_edge_detection(
     model_path: Option<&str>,
     threshold: u8
 ) -> PyResult<Vec<u8>> {
-    // Load image (kept for future ML model preprocessing)
-    let _img = image::load_from_memory(image_bytes)
+    let img = image::load_from_memory(image_bytes)
         .map_err(|e| PyRuntimeError::new_err(format!("Failed to load image: {}", e)))?;
-    
+
     // Check if model is available
     let use_ml = if let Some(path) = model_path {
         PathBuf::from(path).exists()
     } else {
         false
     };
-    
+
     if use_ml {
-        // ML-based edge detection (stub - full implementation requires ort::Session)
-        // For now, we'll use enhanced traditional method
+        let model_path = model_path.unwrap();
+        if let Ok(result) = run_onnx_edge_detection(&img, model_path, threshold) {
+            return Ok(result);
+        }
+        // Inference failed (bad model, shape mismatch, etc.) - degrade to the
+        // enhanced scalar Sobel rather than failing the whole request.
         ai_enhanced_sobel(image_bytes, threshold)
     } else {
         // Fallback to traditional Sobel
-        crate::edge_detection::sobel_edge_detection(image_bytes, threshold)
+        crate::edge_detection::sobel_edge_detection(image_bytes, threshold, false, 0, None)
     }
 }
 
+/// Run a real ONNX forward pass: preprocess to the model's input tensor,
+/// call `model_loader::run_inference`, then postprocess the output back into
+/// a threshold-applied edge map PNG at the source image's resolution.
+fn run_onnx_edge_detection(img: &DynamicImage, model_path: &str, threshold: u8) -> PyResult<Vec<u8>> {
+    let (orig_w, orig_h) = img.dimensions();
+    let (model_w, model_h) = MODEL_INPUT_SIZE;
+
+    let input_data = preprocess_image(img, MODEL_INPUT_SIZE);
+    let input = Array4::from_shape_vec((1, 3, model_h as usize, model_w as usize), input_data)
+        .map_err(|e| PyRuntimeError::new_err(format!("Failed to shape model input: {}", e)))?;
+
+    let session = crate::model_loader::load_model(model_path)?;
+    let input_name = session.inputs.get(0).map(|i| i.name.clone()).unwrap_or_else(|| "input".to_string());
+    let output_name = session.outputs.get(0).map(|o| o.name.clone()).unwrap_or_else(|| "output".to_string());
+
+    let output = crate::model_loader::run_inference(model_path, input, &input_name, &output_name)?;
+    let edges = postprocess_edges(output, model_w, model_h);
+
+    let edge_img = image::ImageBuffer::<image::Luma<u8>, _>::from_raw(model_w, model_h, edges)
+        .ok_or_else(|| PyRuntimeError::new_err("Model output size doesn't match the expected input resolution"))?;
+    let resized = image::imageops::resize(&edge_img, orig_w, orig_h, image::imageops::FilterType::Triangle);
+
+    let thresholded: Vec<u8> = resized.pixels()
+        .map(|p| if p[0] > threshold { 255 } else { 0 })
+        .collect();
+
+    let out_img = image::ImageBuffer::<image::Luma<u8>, _>::from_raw(orig_w, orig_h, thresholded)
+        .ok_or_else(|| PyRuntimeError::new_err("Failed to create edge image"))?;
+
+    let mut png_data = Vec::new();
+    DynamicImage::ImageLuma8(out_img)
+        .write_to(&mut std::io::Cursor::new(&mut png_data), image::ImageFormat::Png)
+        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+
+    Ok(png_data)
+}
+
 /// Enhanced Sobel with multi-scale analysis
 /// This provides better quality than basic Sobel while remaining fast
 fn ai_enhanced_sobel(image_bytes: &[u8], threshold: u8) -> PyResult<Vec<u8>> {
@@ -152,7 +198,7 @@ pub fn blend_edges(
 ) -> Vec<u8> {
     let alpha_clamped = alpha.clamp(0.0, 1.0);
     let beta = 1.0 - alpha_clamped;
-    
+
     traditional.iter()
         .zip(ml_edges.iter())
         .map(|(&t, &m)| {
@@ -162,6 +208,77 @@ pub fn blend_edges(
         .collect()
 }
 
+/// Advanced compositing equation used to combine two edge maps, as an
+/// alternative to `blend_edges`'s plain alpha-weighted average. Each variant
+/// operates per-pixel on the traditional/ML intensities normalized to [0,1].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+    Multiply,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    ColorDodge,
+    ColorBurn,
+    HardLight,
+    SoftLight,
+    Difference,
+    Exclusion,
+}
+
+/// Apply `mode`'s compositing equation to a pair of normalized [0,1]
+/// intensities, `a` (traditional) and `b` (ML).
+fn apply_blend_mode(mode: BlendMode, a: f32, b: f32) -> f32 {
+    match mode {
+        BlendMode::Multiply => a * b,
+        BlendMode::Screen => 1.0 - (1.0 - a) * (1.0 - b),
+        BlendMode::Overlay => {
+            if a < 0.5 { 2.0 * a * b } else { 1.0 - 2.0 * (1.0 - a) * (1.0 - b) }
+        }
+        BlendMode::Darken => a.min(b),
+        BlendMode::Lighten => a.max(b),
+        BlendMode::ColorDodge => {
+            if b >= 1.0 { 1.0 } else { (a / (1.0 - b)).min(1.0) }
+        }
+        BlendMode::ColorBurn => {
+            if b <= 0.0 { 0.0 } else { 1.0 - ((1.0 - a) / b).min(1.0) }
+        }
+        BlendMode::HardLight => {
+            if b < 0.5 { 2.0 * a * b } else { 1.0 - 2.0 * (1.0 - a) * (1.0 - b) }
+        }
+        BlendMode::SoftLight => {
+            let d = if a <= 0.25 { ((16.0 * a - 12.0) * a + 4.0) * a } else { a.sqrt() };
+            if b <= 0.5 {
+                a - (1.0 - 2.0 * b) * a * (1.0 - a)
+            } else {
+                a + (2.0 * b - 1.0) * (d - a)
+            }
+        }
+        BlendMode::Difference => (a - b).abs(),
+        BlendMode::Exclusion => a + b - 2.0 * a * b,
+    }
+}
+
+/// Blend traditional and ML edges using an advanced per-pixel compositing
+/// equation (multiply, screen, overlay, ...) instead of `blend_edges`'s
+/// linear alpha mix. E.g. `Darken` suppresses spurious ML edges that the
+/// clean Sobel map disagrees with; `Screen` unions weak contours from both.
+pub fn blend_edges_with_mode(
+    traditional: &[u8],
+    ml_edges: &[u8],
+    mode: BlendMode
+) -> Vec<u8> {
+    traditional.iter()
+        .zip(ml_edges.iter())
+        .map(|(&t, &m)| {
+            let a = t as f32 / 255.0;
+            let b = m as f32 / 255.0;
+            let combined = apply_blend_mode(mode, a, b) * 255.0;
+            combined.round().clamp(0.0, 255.0) as u8
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -183,4 +300,39 @@ mod tests {
         let blended = blend_edges(&trad, &ml, 1.0);
         assert_eq!(blended, ml);
     }
+
+    #[test]
+    fn test_blend_edges_with_mode_multiply_and_screen() {
+        let trad = vec![255u8, 0, 128];
+        let ml = vec![255u8, 255, 128];
+
+        // multiply: 1*1=1, 0*1=0, 0.5*0.5=0.25
+        let blended = blend_edges_with_mode(&trad, &ml, BlendMode::Multiply);
+        assert_eq!(blended, vec![255, 0, 64]);
+
+        // screen: 1-(1-1)(1-1)=1, 1-(1-0)(1-1)=1, 1-(1-0.5)(1-0.5)=0.75
+        let blended = blend_edges_with_mode(&trad, &ml, BlendMode::Screen);
+        assert_eq!(blended, vec![255, 255, 192]);
+    }
+
+    #[test]
+    fn test_blend_edges_with_mode_darken_suppresses_spurious_ml_edge() {
+        // A clean Sobel edge (0) should suppress a spurious ML edge (255).
+        let trad = vec![0u8];
+        let ml = vec![255u8];
+        let blended = blend_edges_with_mode(&trad, &ml, BlendMode::Darken);
+        assert_eq!(blended, vec![0]);
+    }
+
+    #[test]
+    fn test_blend_edges_with_mode_difference_and_exclusion_agree_at_extremes() {
+        let trad = vec![255u8, 0];
+        let ml = vec![0u8, 0];
+
+        let diff = blend_edges_with_mode(&trad, &ml, BlendMode::Difference);
+        assert_eq!(diff, vec![255, 0]);
+
+        let excl = blend_edges_with_mode(&trad, &ml, BlendMode::Exclusion);
+        assert_eq!(excl, vec![255, 0]);
+    }
 }
@@ -2,6 +2,10 @@
 /// LAB color space is designed to approximate human vision - equal distances
 /// in LAB space correspond to roughly equal perceived color differences.
 
+use pyo3::exceptions::PyValueError;
+use pyo3::PyResult;
+use rand::Rng;
+
 const D65_X: f32 = 95.047;
 const D65_Y: f32 = 100.0;
 const D65_Z: f32 = 108.883;
@@ -54,67 +58,310 @@ pub fn color_distance_lab(l1: f32, a1: f32, b1: f32, l2: f32, a2: f32, b2: f32)
     let dl = l1 - l2;
     let da = a1 - a2;
     let db = b1 - b2;
-    
+
     (dl * dl + da * da + db * db).sqrt()
 }
 
+const XYB_BIAS: f32 = 0.00379;
+
+/// Biased cube root used by the XYB opsin transform: `cbrt(v + b0) - cbrt(b0)`.
+/// Behaves like a log for perceptual compression at low intensities without
+/// the singularity a plain log has at zero.
+fn xyb_bias_cbrt(v: f32) -> f32 {
+    (v + XYB_BIAS).cbrt() - XYB_BIAS.cbrt()
+}
+
+/// Inverse of `xyb_bias_cbrt`.
+fn xyb_bias_cbrt_inv(g: f32) -> f32 {
+    let t = g + XYB_BIAS.cbrt();
+    t * t * t - XYB_BIAS
+}
+
+/// RGB to XYB color space conversion (the opsin space used by JPEG XL).
+///
+/// XYB mixes linear RGB into three LMS-like "opsin" channels, applies a
+/// biased cube root to each, then recombines them into an X/B chroma pair
+/// and a Y luma channel. It tends to separate luma from chroma more cleanly
+/// than LAB for high-frequency/gradient content.
+/// Input: RGB values in [0, 255]. Output: (X, Y, B).
+pub fn rgb_to_xyb(r: u8, g: u8, b: u8) -> (f32, f32, f32) {
+    let r_linear = gamma_to_linear(r as f32 / 255.0);
+    let g_linear = gamma_to_linear(g as f32 / 255.0);
+    let b_linear = gamma_to_linear(b as f32 / 255.0);
+
+    let l_mix = 0.3 * r_linear + 0.622 * g_linear + 0.078 * b_linear;
+    let m_mix = 0.23 * r_linear + 0.692 * g_linear + 0.078 * b_linear;
+    let s_mix = 0.24342 * r_linear + 0.20476 * g_linear + 0.55182 * b_linear;
+
+    let l_gamma = xyb_bias_cbrt(l_mix);
+    let m_gamma = xyb_bias_cbrt(m_mix);
+    let s_gamma = xyb_bias_cbrt(s_mix);
+
+    let x = (l_gamma - m_gamma) / 2.0;
+    let y = (l_gamma + m_gamma) / 2.0;
+    let b_chan = s_gamma;
+
+    (x, y, b_chan)
+}
+
+/// XYB to RGB color space conversion (inverse of `rgb_to_xyb`).
+/// Input: (X, Y, B). Output: RGB values in [0, 255].
+pub fn xyb_to_rgb(x: f32, y: f32, b: f32) -> (u8, u8, u8) {
+    let l_gamma = y + x;
+    let m_gamma = y - x;
+    let s_gamma = b;
+
+    let l_mix = xyb_bias_cbrt_inv(l_gamma);
+    let m_mix = xyb_bias_cbrt_inv(m_gamma);
+    let s_mix = xyb_bias_cbrt_inv(s_gamma);
+
+    // Inverse of the opsin mix matrix used by `rgb_to_xyb`.
+    let r_linear = 11.031559905208127 * l_mix - 9.866940429457843 * m_mix - 0.16461947575028493 * s_mix;
+    let g_linear = -3.2541543805061606 * l_mix + 4.418773856256446 * m_mix - 0.16461947575028493 * s_mix;
+    let b_linear = -3.6587685498411093 * l_mix + 2.7128819262287673 * m_mix + 1.9458866236123422 * s_mix;
+
+    let r = (linear_to_gamma(r_linear) * 255.0).round().clamp(0.0, 255.0) as u8;
+    let g = (linear_to_gamma(g_linear) * 255.0).round().clamp(0.0, 255.0) as u8;
+    let b = (linear_to_gamma(b_linear) * 255.0).round().clamp(0.0, 255.0) as u8;
+
+    (r, g, b)
+}
+
+/// Which perceptual color space `kmeans_lab` clusters in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// CIE L*a*b*, perceptually uniform and the default for general content.
+    Lab,
+    /// XYB (the JPEG XL opsin space), which can separate chroma from luma
+    /// more cleanly than LAB for gradient-heavy images.
+    Xyb,
+}
+
+/// Which Delta E formula `kmeans_lab` uses when comparing LAB colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DistanceMetric {
+    /// Plain Euclidean distance in LAB space (ΔE76). Cheap and SIMD-friendly.
+    Euclidean,
+    /// CIEDE2000 (ΔE00), which corrects for perceptual non-uniformity in LAB
+    /// (overstated differences in saturated blues, understated near neutrals).
+    /// Defined in terms of LAB's hue/chroma geometry, so pair it with
+    /// `ColorSpace::Lab`; with `ColorSpace::Xyb` it still runs but its
+    /// hue-angle correction no longer has a perceptual basis.
+    Ciede2000,
+}
+
+/// CIEDE2000 perceptual color distance (ΔE00) between two LAB colors.
+///
+/// More perceptually accurate than plain Euclidean ΔE76, at the cost of
+/// being scalar-only (the hue-angle terms don't vectorize cleanly).
+pub fn color_distance_ciede2000(l1: f32, a1: f32, b1: f32, l2: f32, a2: f32, b2: f32) -> f32 {
+    let c1 = (a1 * a1 + b1 * b1).sqrt();
+    let c2 = (a2 * a2 + b2 * b2).sqrt();
+    let c_bar = (c1 + c2) / 2.0;
+
+    let c_bar7 = c_bar.powi(7);
+    let g = 0.5 * (1.0 - (c_bar7 / (c_bar7 + 25f32.powi(7))).sqrt());
+
+    let a1_prime = a1 * (1.0 + g);
+    let a2_prime = a2 * (1.0 + g);
+
+    let c1_prime = (a1_prime * a1_prime + b1 * b1).sqrt();
+    let c2_prime = (a2_prime * a2_prime + b2 * b2).sqrt();
+
+    let h1_prime = hue_prime_degrees(a1_prime, b1);
+    let h2_prime = hue_prime_degrees(a2_prime, b2);
+
+    let delta_l_prime = l2 - l1;
+    let delta_c_prime = c2_prime - c1_prime;
+
+    let delta_h_prime_raw = if c1_prime * c2_prime == 0.0 {
+        0.0
+    } else {
+        let mut diff = h2_prime - h1_prime;
+        if diff > 180.0 {
+            diff -= 360.0;
+        } else if diff < -180.0 {
+            diff += 360.0;
+        }
+        diff
+    };
+    let delta_h_prime = 2.0 * (c1_prime * c2_prime).sqrt() * (delta_h_prime_raw.to_radians() / 2.0).sin();
+
+    let l_bar_prime = (l1 + l2) / 2.0;
+    let c_bar_prime = (c1_prime + c2_prime) / 2.0;
+
+    let h_bar_prime = if c1_prime * c2_prime == 0.0 {
+        h1_prime + h2_prime
+    } else if (h1_prime - h2_prime).abs() > 180.0 {
+        if h1_prime + h2_prime < 360.0 {
+            (h1_prime + h2_prime + 360.0) / 2.0
+        } else {
+            (h1_prime + h2_prime - 360.0) / 2.0
+        }
+    } else {
+        (h1_prime + h2_prime) / 2.0
+    };
+
+    let t = 1.0
+        - 0.17 * (h_bar_prime - 30.0).to_radians().cos()
+        + 0.24 * (2.0 * h_bar_prime).to_radians().cos()
+        + 0.32 * (3.0 * h_bar_prime + 6.0).to_radians().cos()
+        - 0.20 * (4.0 * h_bar_prime - 63.0).to_radians().cos();
+
+    let l_term = l_bar_prime - 50.0;
+    let s_l = 1.0 + (0.015 * l_term * l_term) / (20.0 + l_term * l_term).sqrt();
+    let s_c = 1.0 + 0.045 * c_bar_prime;
+    let s_h = 1.0 + 0.015 * c_bar_prime * t;
+
+    let c_bar_prime7 = c_bar_prime.powi(7);
+    let delta_theta = 30.0 * (-((h_bar_prime - 275.0) / 25.0).powi(2)).exp();
+    let r_c = 2.0 * (c_bar_prime7 / (c_bar_prime7 + 25f32.powi(7))).sqrt();
+    let r_t = -r_c * (2.0 * delta_theta).to_radians().sin();
+
+    let term_l = delta_l_prime / s_l;
+    let term_c = delta_c_prime / s_c;
+    let term_h = delta_h_prime / s_h;
+
+    (term_l * term_l + term_c * term_c + term_h * term_h + r_t * term_c * term_h).sqrt()
+}
+
+/// `atan2(b, a)` in degrees, wrapped into `[0, 360)`, as used by CIEDE2000's
+/// hue-angle terms.
+fn hue_prime_degrees(a: f32, b: f32) -> f32 {
+    if a == 0.0 && b == 0.0 {
+        return 0.0;
+    }
+    let degrees = b.atan2(a).to_degrees();
+    if degrees < 0.0 { degrees + 360.0 } else { degrees }
+}
+
 /// K-means clustering in LAB space for perceptually uniform color quantization
 /// This produces much better results than RGB-based k-means
-pub fn kmeans_lab(pixels_rgb: &[(u8, u8, u8)], k: usize, max_iter: usize) -> Vec<(u8, u8, u8)> {
+///
+/// `mask` is an optional per-pixel inclusion mask (e.g. built from an alpha
+/// threshold); masked-out pixels are still assigned a nearest centroid but
+/// never contribute to centroid seeding or the update step, so fully
+/// transparent background doesn't pull the palette toward itself.
+///
+/// `metric` selects the Delta E formula used during the assignment step.
+/// `DistanceMetric::Euclidean` can use the SIMD batch-distance routine;
+/// `DistanceMetric::Ciede2000` falls back to a scalar per-pixel loop since
+/// its hue-angle terms don't vectorize.
+///
+/// `space` selects which perceptual color space pixels are clustered in.
+/// `color_distance_batch_optimized`/`color_distance_ciede2000` just operate
+/// on three float channels, so the same assignment/update loop works for
+/// `ColorSpace::Xyb` unchanged — only the RGB<->space conversion differs.
+///
+/// `profile` is an optional device-RGB color-management transform (see
+/// `ColorTransform`). When `space` is `ColorSpace::Lab` and `profile` is
+/// `Some`, pixels are converted with `rgb_to_lab_with_profile`/
+/// `lab_to_rgb_with_profile` instead of assuming sRGB; it's ignored for
+/// `ColorSpace::Xyb`, which doesn't yet have a profile-aware conversion.
+pub fn kmeans_lab(
+    pixels_rgb: &[(u8, u8, u8)],
+    k: usize,
+    max_iter: usize,
+    mask: Option<&[bool]>,
+    metric: DistanceMetric,
+    space: ColorSpace,
+    profile: Option<&ColorTransform>,
+) -> Vec<(u8, u8, u8)> {
     if pixels_rgb.is_empty() || k == 0 {
         return Vec::new();
     }
-    
-    // Convert all pixels to LAB
-    let pixels_lab: Vec<(f32, f32, f32)> = pixels_rgb.iter()
-        .map(|&(r, g, b)| rgb_to_lab(r, g, b))
-        .collect();
-    
+
+    // Convert all pixels to the chosen space, kept as a struct-of-arrays
+    // buffer so the assignment step below can hand whole channel slices to
+    // the SIMD batch-distance routine instead of looping per pixel.
+    let n = pixels_rgb.len();
+    let mut l_buf = Vec::with_capacity(n);
+    let mut a_buf = Vec::with_capacity(n);
+    let mut b_buf = Vec::with_capacity(n);
+    for &(r, g, b) in pixels_rgb {
+        let (l, a, bb) = match (space, profile) {
+            (ColorSpace::Lab, Some(transform)) => rgb_to_lab_with_profile(r, g, b, transform),
+            (ColorSpace::Lab, None) => rgb_to_lab(r, g, b),
+            (ColorSpace::Xyb, _) => rgb_to_xyb(r, g, b),
+        };
+        l_buf.push(l);
+        a_buf.push(a);
+        b_buf.push(bb);
+    }
+
     // Initialize centroids (k-means++ would be better, but simple sampling for now)
-    let step = (pixels_lab.len() / k).max(1);
-    let mut centroids: Vec<(f32, f32, f32)> = pixels_lab.iter()
+    let active_indices: Vec<usize> = match mask {
+        Some(m) => (0..n).filter(|&i| m[i]).collect(),
+        None => (0..n).collect(),
+    };
+    let seed_pool: Vec<usize> = if active_indices.is_empty() {
+        (0..n).collect()
+    } else {
+        active_indices
+    };
+
+    let step = (seed_pool.len() / k).max(1);
+    let mut centroids: Vec<(f32, f32, f32)> = seed_pool.iter()
         .step_by(step)
         .take(k)
-        .cloned()
+        .map(|&i| (l_buf[i], a_buf[i], b_buf[i]))
         .collect();
-    
+
     // Ensure we have exactly k centroids
     while centroids.len() < k {
-        centroids.push(pixels_lab[0]);
+        let i = seed_pool[0];
+        centroids.push((l_buf[i], a_buf[i], b_buf[i]));
     }
-    
-    let mut assignments = vec![0usize; pixels_lab.len()];
-    
+
+    let mut assignments = vec![0usize; n];
+    let mut min_dist = vec![f32::MAX; n];
+    let mut scratch = vec![0f32; n];
+
     // K-means iterations
     for _ in 0..max_iter {
-        // Assignment step
-        for (i, &(l, a, b)) in pixels_lab.iter().enumerate() {
-            let mut min_dist = f32::MAX;
-            let mut best_cluster = 0;
-            
-            for (ci, &(cl, ca, cb)) in centroids.iter().enumerate() {
-                let dist = color_distance_lab(l, a, b, cl, ca, cb);
-                if dist < min_dist {
-                    min_dist = dist;
-                    best_cluster = ci;
+        // Assignment step: for each centroid, compute its distance to every
+        // pixel in one SIMD batch call, then keep the running per-pixel
+        // minimum. This is equivalent to the per-pixel/per-centroid nested
+        // loop but lets color_distance_batch_optimized vectorize the inner
+        // channel math.
+        min_dist.iter_mut().for_each(|d| *d = f32::MAX);
+        for (ci, &(cl, ca, cb)) in centroids.iter().enumerate() {
+            match metric {
+                DistanceMetric::Euclidean => {
+                    crate::simd_ops::color_distance_batch_optimized(&l_buf, &a_buf, &b_buf, (cl, ca, cb), &mut scratch);
+                }
+                DistanceMetric::Ciede2000 => {
+                    for i in 0..n {
+                        scratch[i] = color_distance_ciede2000(l_buf[i], a_buf[i], b_buf[i], cl, ca, cb);
+                    }
+                }
+            }
+            for i in 0..n {
+                if scratch[i] < min_dist[i] {
+                    min_dist[i] = scratch[i];
+                    assignments[i] = ci;
                 }
             }
-            
-            assignments[i] = best_cluster;
         }
-        
+
         // Update step
         let mut sums = vec![(0.0f32, 0.0f32, 0.0f32); k];
         let mut counts = vec![0u32; k];
-        
-        for (i, &(l, a, b)) in pixels_lab.iter().enumerate() {
+
+        for i in 0..n {
+            if let Some(m) = mask {
+                if !m[i] {
+                    continue;
+                }
+            }
+
             let cluster = assignments[i];
-            sums[cluster].0 += l;
-            sums[cluster].1 += a;
-            sums[cluster].2 += b;
+            sums[cluster].0 += l_buf[i];
+            sums[cluster].1 += a_buf[i];
+            sums[cluster].2 += b_buf[i];
             counts[cluster] += 1;
         }
-        
+
         for i in 0..k {
             if counts[i] > 0 {
                 let count = counts[i] as f32;
@@ -126,13 +373,544 @@ pub fn kmeans_lab(pixels_rgb: &[(u8, u8, u8)], k: usize, max_iter: usize) -> Vec
             }
         }
     }
-    
+
     // Convert centroids back to RGB
     centroids.iter()
-        .map(|&(l, a, b)| lab_to_rgb(l, a, b))
+        .map(|&(l, a, b)| match (space, profile) {
+            (ColorSpace::Lab, Some(transform)) => lab_to_rgb_with_profile(l, a, b, transform),
+            (ColorSpace::Lab, None) => lab_to_rgb(l, a, b),
+            (ColorSpace::Xyb, _) => xyb_to_rgb(l, a, b),
+        })
         .collect()
 }
 
+/// Per-channel perceptual weighting applied when comparing colors, so
+/// quantization error tracks perceived difference rather than raw LAB
+/// Euclidean distance: lightness differences are less perceptually salient
+/// than chroma differences, and alpha gets its own weighted coordinate so
+/// translucent and opaque pixels of the same color don't collapse into one
+/// cluster.
+#[derive(Debug, Clone, Copy)]
+pub struct PerceptualWeights {
+    pub lightness: f32,
+    pub chroma: f32,
+    pub alpha: f32,
+}
+
+impl Default for PerceptualWeights {
+    fn default() -> Self {
+        PerceptualWeights { lightness: 0.6, chroma: 1.0, alpha: 0.5 }
+    }
+}
+
+/// Palette plus per-pixel index map produced by `quantize_palette_rgba`.
+pub struct PaletteQuantization {
+    pub palette: Vec<(u8, u8, u8, u8)>,
+    pub indices: Vec<usize>,
+}
+
+/// A point in (L, a, b, alpha-fraction) space.
+type ColorPoint = (f32, f32, f32, f32);
+
+/// Floor contribution weight for fully transparent pixels during centroid
+/// updates (k-means++ seeding does not use it): never zero (so a cluster made up only of transparent
+/// pixels still converges) but small enough that transparent color noise
+/// can't drag a visible cluster's centroid toward it.
+const TRANSPARENT_FLOOR: f32 = 0.05;
+
+fn weighted_distance(p1: ColorPoint, p2: ColorPoint, weights: PerceptualWeights) -> f32 {
+    let dl = (p1.0 - p2.0) * weights.lightness;
+    let da = (p1.1 - p2.1) * weights.chroma;
+    let db = (p1.2 - p2.2) * weights.chroma;
+    let dalpha = (p1.3 - p2.3) * 100.0 * weights.alpha;
+    (dl * dl + da * da + db * db + dalpha * dalpha).sqrt()
+}
+
+fn contribution_weight(alpha_frac: f32) -> f32 {
+    TRANSPARENT_FLOOR + (1.0 - TRANSPARENT_FLOOR) * alpha_frac
+}
+
+/// Pick `k` initial centroids via k-means++: the first uniformly at random,
+/// then each subsequent one with probability proportional to its squared
+/// distance to the nearest already-chosen centroid. This avoids the empty-
+/// or duplicate-cluster problems that plain strided sampling is prone to.
+fn kmeans_plusplus_seed(points: &[ColorPoint], k: usize, weights: PerceptualWeights) -> Vec<ColorPoint> {
+    let mut rng = rand::thread_rng();
+    let n = points.len();
+    let mut centroids = Vec::with_capacity(k);
+
+    let first = rng.gen_range(0..n);
+    centroids.push(points[first]);
+
+    let mut best_dist_sq: Vec<f32> = points.iter()
+        .map(|&p| {
+            let d = weighted_distance(p, points[first], weights);
+            d * d
+        })
+        .collect();
+
+    while centroids.len() < k {
+        let total: f32 = best_dist_sq.iter().sum();
+
+        let chosen = if total <= 0.0 {
+            rng.gen_range(0..n)
+        } else {
+            let target = rng.gen::<f32>() * total;
+            let mut cumulative = 0f32;
+            let mut idx = n - 1;
+            for i in 0..n {
+                cumulative += best_dist_sq[i];
+                if cumulative >= target {
+                    idx = i;
+                    break;
+                }
+            }
+            idx
+        };
+
+        let new_centroid = points[chosen];
+        centroids.push(new_centroid);
+
+        for i in 0..n {
+            let d = weighted_distance(points[i], new_centroid, weights);
+            let d_sq = d * d;
+            if d_sq < best_dist_sq[i] {
+                best_dist_sq[i] = d_sq;
+            }
+        }
+    }
+
+    centroids
+}
+
+/// Alpha-aware color quantization with k-means++ seeding and perceptual
+/// channel weighting. Unlike `kmeans_lab`, alpha is a weighted coordinate in
+/// the clustering space rather than a hard include/exclude mask, so
+/// translucent pixels land in whichever cluster actually matches their
+/// color and opacity instead of being forced in or out of the palette.
+/// Any cluster that goes empty during an update is re-seeded from the point
+/// with the largest current distance to its assigned centroid.
+///
+/// Returns the RGBA palette alongside a per-pixel index map so callers can
+/// build an indexed/paletted image.
+pub fn quantize_palette_rgba(
+    pixels_rgba: &[(u8, u8, u8, u8)],
+    k: usize,
+    max_iter: usize,
+    weights: PerceptualWeights,
+) -> PaletteQuantization {
+    if pixels_rgba.is_empty() || k == 0 {
+        return PaletteQuantization { palette: Vec::new(), indices: Vec::new() };
+    }
+
+    let n = pixels_rgba.len();
+    let k = k.min(n);
+
+    let points: Vec<ColorPoint> = pixels_rgba.iter()
+        .map(|&(r, g, b, a)| {
+            let (l, a_lab, b_lab) = rgb_to_lab(r, g, b);
+            (l, a_lab, b_lab, a as f32 / 255.0)
+        })
+        .collect();
+
+    let mut centroids = kmeans_plusplus_seed(&points, k, weights);
+    let mut assignments = vec![0usize; n];
+
+    for _ in 0..max_iter {
+        // Assignment step
+        for i in 0..n {
+            let mut min_dist = f32::MAX;
+            let mut best = 0;
+            for (ci, &c) in centroids.iter().enumerate() {
+                let d = weighted_distance(points[i], c, weights);
+                if d < min_dist {
+                    min_dist = d;
+                    best = ci;
+                }
+            }
+            assignments[i] = best;
+        }
+
+        // Update step: weighted mean, down-weighting near-transparent pixels
+        // so they can't pull a centroid toward whatever color sits behind them.
+        let mut sums = vec![(0f32, 0f32, 0f32, 0f32); k];
+        let mut weight_totals = vec![0f32; k];
+        for i in 0..n {
+            let ci = assignments[i];
+            let w = contribution_weight(points[i].3);
+            sums[ci].0 += points[i].0 * w;
+            sums[ci].1 += points[i].1 * w;
+            sums[ci].2 += points[i].2 * w;
+            sums[ci].3 += points[i].3 * w;
+            weight_totals[ci] += w;
+        }
+
+        for ci in 0..k {
+            if weight_totals[ci] > 0.0 {
+                let wt = weight_totals[ci];
+                centroids[ci] = (sums[ci].0 / wt, sums[ci].1 / wt, sums[ci].2 / wt, sums[ci].3 / wt);
+            } else {
+                // Re-seed from the point with the largest current error so
+                // this cluster doesn't stay dead for the rest of the run.
+                let mut worst_idx = 0;
+                let mut worst_dist = -1f32;
+                for i in 0..n {
+                    let d = weighted_distance(points[i], centroids[assignments[i]], weights);
+                    if d > worst_dist {
+                        worst_dist = d;
+                        worst_idx = i;
+                    }
+                }
+                centroids[ci] = points[worst_idx];
+                assignments[worst_idx] = ci;
+            }
+        }
+    }
+
+    let palette: Vec<(u8, u8, u8, u8)> = centroids.iter()
+        .map(|&(l, a, b, alpha_frac)| {
+            let (r, g, bch) = lab_to_rgb(l, a, b);
+            let alpha = (alpha_frac * 255.0).round().clamp(0.0, 255.0) as u8;
+            (r, g, bch, alpha)
+        })
+        .collect();
+
+    PaletteQuantization { palette, indices: assignments }
+}
+
+// ---------------------------------------------------------------------
+// ICC profile-aware color management
+//
+// `rgb_to_lab` assumes sRGB. Real-world images can be tagged AdobeRGB,
+// Display P3, or a camera's own working space, and converting those as if
+// they were sRGB poisons every downstream LAB computation (k-means,
+// saliency, edge detection). `ColorTransform` precomputes a device-RGB ->
+// XYZ(D65) transform once (TRC lookup tables + a combined 3x3 matrix) so
+// `rgb_to_lab_with_profile` is just a LUT lookup and a matrix multiply.
+// ---------------------------------------------------------------------
+
+/// Bradford cone-response matrix and its inverse, used to chromatically
+/// adapt a profile's native white point to D65.
+const BRADFORD_MA: [[f32; 3]; 3] = [
+    [0.8951, 0.2664, -0.1614],
+    [-0.7502, 1.7135, 0.0367],
+    [0.0389, -0.0685, 1.0296],
+];
+const BRADFORD_MA_INV: [[f32; 3]; 3] = [
+    [0.9869929, -0.1470543, 0.1599627],
+    [0.4323053, 0.5183603, 0.0492912],
+    [-0.0085287, 0.0400428, 0.9684867],
+];
+
+const D50_WHITE: [f32; 3] = [0.9642, 1.0, 0.8249];
+const D65_WHITE: [f32; 3] = [0.95047, 1.0, 1.08883];
+
+fn mat_mul3(a: [[f32; 3]; 3], b: [[f32; 3]; 3]) -> [[f32; 3]; 3] {
+    let mut out = [[0f32; 3]; 3];
+    for i in 0..3 {
+        for j in 0..3 {
+            out[i][j] = a[i][0] * b[0][j] + a[i][1] * b[1][j] + a[i][2] * b[2][j];
+        }
+    }
+    out
+}
+
+fn mat_vec3(m: [[f32; 3]; 3], v: [f32; 3]) -> [f32; 3] {
+    [
+        m[0][0] * v[0] + m[0][1] * v[1] + m[0][2] * v[2],
+        m[1][0] * v[0] + m[1][1] * v[1] + m[1][2] * v[2],
+        m[2][0] * v[0] + m[2][1] * v[1] + m[2][2] * v[2],
+    ]
+}
+
+/// Bradford chromatic adaptation matrix mapping XYZ under `src_white` to
+/// XYZ under `dst_white` (both XYZ triples normalized to Y=1).
+fn bradford_adaptation(src_white: [f32; 3], dst_white: [f32; 3]) -> [[f32; 3]; 3] {
+    let src_cone = mat_vec3(BRADFORD_MA, src_white);
+    let dst_cone = mat_vec3(BRADFORD_MA, dst_white);
+
+    let scale = [
+        [dst_cone[0] / src_cone[0], 0.0, 0.0],
+        [0.0, dst_cone[1] / src_cone[1], 0.0],
+        [0.0, 0.0, dst_cone[2] / src_cone[2]],
+    ];
+
+    mat_mul3(BRADFORD_MA_INV, mat_mul3(scale, BRADFORD_MA))
+}
+
+/// Well-known RGB working spaces `ColorTransform::for_working_space` can
+/// build directly, without parsing an actual ICC file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkingSpace {
+    Srgb,
+    AdobeRgb,
+    DisplayP3,
+}
+
+/// A precomputed device-RGB -> XYZ(D65) transform: a 256-entry TRC lookup
+/// table per channel (device code value -> linear light) plus a combined
+/// 3x3 matrix (the profile's colorant matrix composed with Bradford
+/// chromatic adaptation, if the profile's white point isn't already D65).
+pub struct ColorTransform {
+    trc_r: [f32; 256],
+    trc_g: [f32; 256],
+    trc_b: [f32; 256],
+    matrix: [[f32; 3]; 3],
+}
+
+impl ColorTransform {
+    /// Build a transform for a well-known working space without parsing a
+    /// profile. sRGB, Adobe RGB, and Display P3 are all D65-native, so no
+    /// Bradford adaptation is needed here.
+    pub fn for_working_space(space: WorkingSpace) -> ColorTransform {
+        let matrix = match space {
+            WorkingSpace::Srgb => [
+                [0.4124564, 0.3575761, 0.1804375],
+                [0.2126729, 0.7151522, 0.0721750],
+                [0.0193339, 0.1191920, 0.9503041],
+            ],
+            WorkingSpace::AdobeRgb => [
+                [0.5767309, 0.1855540, 0.1881852],
+                [0.2973769, 0.6273491, 0.0752741],
+                [0.0270343, 0.0706872, 0.9911085],
+            ],
+            WorkingSpace::DisplayP3 => [
+                [0.4865709, 0.2656677, 0.1982173],
+                [0.2289746, 0.6917385, 0.0792869],
+                [0.0000000, 0.0451134, 1.0439444],
+            ],
+        };
+
+        let trc = match space {
+            // Display P3 reuses the sRGB piecewise tone curve.
+            WorkingSpace::Srgb | WorkingSpace::DisplayP3 => build_srgb_trc_lut(),
+            WorkingSpace::AdobeRgb => build_pure_gamma_trc_lut(2.19921875),
+        };
+
+        ColorTransform { trc_r: trc, trc_g: trc, trc_b: trc, matrix }
+    }
+
+    /// Parse a "matrix/TRC" ICC profile — the model used by sRGB, Adobe
+    /// RGB, and most camera/working-space profiles — by reading the
+    /// `rXYZ`/`gXYZ`/`bXYZ` colorant tags and `rTRC`/`gTRC`/`bTRC` tone
+    /// curves out of the tag table. Colorant `XYZ` tags are always relative
+    /// to the D50 profile connection space per the ICC spec, so the
+    /// resulting matrix is composed with a Bradford D50->D65 adaptation.
+    pub fn from_icc_profile(icc_bytes: &[u8]) -> PyResult<ColorTransform> {
+        let tags = parse_icc_tag_table(icc_bytes)?;
+
+        let r_xyz = read_icc_xyz_tag(icc_bytes, &tags, b"rXYZ")?;
+        let g_xyz = read_icc_xyz_tag(icc_bytes, &tags, b"gXYZ")?;
+        let b_xyz = read_icc_xyz_tag(icc_bytes, &tags, b"bXYZ")?;
+
+        let colorant_matrix = [
+            [r_xyz[0], g_xyz[0], b_xyz[0]],
+            [r_xyz[1], g_xyz[1], b_xyz[1]],
+            [r_xyz[2], g_xyz[2], b_xyz[2]],
+        ];
+
+        let adaptation = bradford_adaptation(D50_WHITE, D65_WHITE);
+        let matrix = mat_mul3(adaptation, colorant_matrix);
+
+        let trc_r = read_icc_trc_tag(icc_bytes, &tags, b"rTRC")?;
+        let trc_g = read_icc_trc_tag(icc_bytes, &tags, b"gTRC")?;
+        let trc_b = read_icc_trc_tag(icc_bytes, &tags, b"bTRC")?;
+
+        Ok(ColorTransform { trc_r, trc_g, trc_b, matrix })
+    }
+
+    fn linearize(&self, r: u8, g: u8, b: u8) -> (f32, f32, f32) {
+        (self.trc_r[r as usize], self.trc_g[g as usize], self.trc_b[b as usize])
+    }
+
+    /// Invert one channel's TRC lookup table: find the device code value
+    /// whose linear-light output is closest to `linear` via binary search
+    /// (the LUT is monotonically increasing by construction).
+    fn delinearize_channel(trc: &[f32; 256], linear: f32) -> u8 {
+        let linear = linear.clamp(0.0, 1.0);
+        let mut lo = 0usize;
+        let mut hi = 255usize;
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            if trc[mid] < linear {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        if lo > 0 && linear - trc[lo - 1] < trc[lo] - linear {
+            lo -= 1;
+        }
+        lo as u8
+    }
+
+    fn delinearize(&self, r_linear: f32, g_linear: f32, b_linear: f32) -> (u8, u8, u8) {
+        (
+            Self::delinearize_channel(&self.trc_r, r_linear),
+            Self::delinearize_channel(&self.trc_g, g_linear),
+            Self::delinearize_channel(&self.trc_b, b_linear),
+        )
+    }
+}
+
+/// Invert a general 3x3 matrix (used to go from the profile's RGB->XYZ
+/// matrix back to XYZ->RGB for `lab_to_rgb_with_profile`).
+fn invert_mat3(m: [[f32; 3]; 3]) -> [[f32; 3]; 3] {
+    let det = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+    let inv_det = 1.0 / det;
+
+    [
+        [
+            (m[1][1] * m[2][2] - m[1][2] * m[2][1]) * inv_det,
+            (m[0][2] * m[2][1] - m[0][1] * m[2][2]) * inv_det,
+            (m[0][1] * m[1][2] - m[0][2] * m[1][1]) * inv_det,
+        ],
+        [
+            (m[1][2] * m[2][0] - m[1][0] * m[2][2]) * inv_det,
+            (m[0][0] * m[2][2] - m[0][2] * m[2][0]) * inv_det,
+            (m[0][2] * m[1][0] - m[0][0] * m[1][2]) * inv_det,
+        ],
+        [
+            (m[1][0] * m[2][1] - m[1][1] * m[2][0]) * inv_det,
+            (m[0][1] * m[2][0] - m[0][0] * m[2][1]) * inv_det,
+            (m[0][0] * m[1][1] - m[0][1] * m[1][0]) * inv_det,
+        ],
+    ]
+}
+
+fn build_srgb_trc_lut() -> [f32; 256] {
+    let mut lut = [0f32; 256];
+    for (i, entry) in lut.iter_mut().enumerate() {
+        *entry = gamma_to_linear(i as f32 / 255.0);
+    }
+    lut
+}
+
+fn build_pure_gamma_trc_lut(gamma: f32) -> [f32; 256] {
+    let mut lut = [0f32; 256];
+    for (i, entry) in lut.iter_mut().enumerate() {
+        *entry = (i as f32 / 255.0).powf(gamma);
+    }
+    lut
+}
+
+struct IccTag {
+    signature: [u8; 4],
+    offset: u32,
+}
+
+fn parse_icc_tag_table(data: &[u8]) -> PyResult<Vec<IccTag>> {
+    if data.len() < 132 {
+        return Err(PyValueError::new_err("ICC profile too short to contain a tag table"));
+    }
+    let tag_count = u32::from_be_bytes([data[128], data[129], data[130], data[131]]) as usize;
+
+    let mut tags = Vec::with_capacity(tag_count);
+    for i in 0..tag_count {
+        let base = 132 + i * 12;
+        if base + 12 > data.len() {
+            return Err(PyValueError::new_err("ICC tag table truncated"));
+        }
+        let signature = [data[base], data[base + 1], data[base + 2], data[base + 3]];
+        let offset = u32::from_be_bytes([data[base + 4], data[base + 5], data[base + 6], data[base + 7]]);
+        tags.push(IccTag { signature, offset });
+    }
+    Ok(tags)
+}
+
+fn find_icc_tag<'a>(tags: &'a [IccTag], signature: &[u8; 4]) -> Option<&'a IccTag> {
+    tags.iter().find(|t| &t.signature == signature)
+}
+
+fn s15fixed16_to_f32(bytes: [u8; 4]) -> f32 {
+    i32::from_be_bytes(bytes) as f32 / 65536.0
+}
+
+fn read_icc_xyz_tag(data: &[u8], tags: &[IccTag], signature: &[u8; 4]) -> PyResult<[f32; 3]> {
+    let tag = find_icc_tag(tags, signature)
+        .ok_or_else(|| PyValueError::new_err(format!("Missing ICC tag {}", tag_name(signature))))?;
+
+    let start = tag.offset as usize;
+    if start + 20 > data.len() {
+        return Err(PyValueError::new_err("ICC XYZ tag out of bounds"));
+    }
+
+    // XYZType layout: 4-byte type signature, 4 reserved bytes, then one
+    // XYZNumber (three s15Fixed16Number, 4 bytes each).
+    Ok([
+        s15fixed16_to_f32([data[start + 8], data[start + 9], data[start + 10], data[start + 11]]),
+        s15fixed16_to_f32([data[start + 12], data[start + 13], data[start + 14], data[start + 15]]),
+        s15fixed16_to_f32([data[start + 16], data[start + 17], data[start + 18], data[start + 19]]),
+    ])
+}
+
+fn read_icc_trc_tag(data: &[u8], tags: &[IccTag], signature: &[u8; 4]) -> PyResult<[f32; 256]> {
+    let tag = find_icc_tag(tags, signature)
+        .ok_or_else(|| PyValueError::new_err(format!("Missing ICC tag {}", tag_name(signature))))?;
+
+    let start = tag.offset as usize;
+    if start + 12 > data.len() {
+        return Err(PyValueError::new_err("ICC curv tag out of bounds"));
+    }
+    let count = u32::from_be_bytes([data[start + 8], data[start + 9], data[start + 10], data[start + 11]]) as usize;
+
+    let mut lut = [0f32; 256];
+    if count == 0 {
+        // Zero entries means an identity (already-linear) curve.
+        for (i, entry) in lut.iter_mut().enumerate() {
+            *entry = i as f32 / 255.0;
+        }
+    } else if count == 1 {
+        // Single u8Fixed8Number gamma value.
+        if start + 14 > data.len() {
+            return Err(PyValueError::new_err("ICC curv tag out of bounds"));
+        }
+        let raw = u16::from_be_bytes([data[start + 12], data[start + 13]]);
+        let gamma = raw as f32 / 256.0;
+        for (i, entry) in lut.iter_mut().enumerate() {
+            *entry = (i as f32 / 255.0).powf(gamma);
+        }
+    } else {
+        // Sampled curve: `count` u16 entries spanning [0, 65535]; resample
+        // to our 256-entry device-value LUT via nearest-neighbor lookup.
+        for (i, entry) in lut.iter_mut().enumerate() {
+            let t = i as f32 / 255.0;
+            let idx = ((t * (count as f32 - 1.0)).round() as usize).min(count - 1);
+            let entry_start = start + 12 + idx * 2;
+            if entry_start + 2 > data.len() {
+                return Err(PyValueError::new_err("ICC curv tag out of bounds"));
+            }
+            let raw = u16::from_be_bytes([data[entry_start], data[entry_start + 1]]);
+            *entry = raw as f32 / 65535.0;
+        }
+    }
+
+    Ok(lut)
+}
+
+fn tag_name(signature: &[u8; 4]) -> String {
+    std::str::from_utf8(signature).unwrap_or("????").to_string()
+}
+
+/// RGB to LAB using a precomputed `ColorTransform` instead of the hard-coded
+/// sRGB gamma/matrix in `rgb_to_lab`, so profiles like Adobe RGB, Display
+/// P3, or a parsed camera ICC profile convert colorimetrically correctly.
+pub fn rgb_to_lab_with_profile(r: u8, g: u8, b: u8, transform: &ColorTransform) -> (f32, f32, f32) {
+    let (r_lin, g_lin, b_lin) = transform.linearize(r, g, b);
+    let xyz = mat_vec3(transform.matrix, [r_lin, g_lin, b_lin]);
+    xyz_to_lab(xyz[0] * 100.0, xyz[1] * 100.0, xyz[2] * 100.0)
+}
+
+/// Inverse of `rgb_to_lab_with_profile`: LAB -> device RGB under `transform`.
+pub fn lab_to_rgb_with_profile(l: f32, a: f32, b: f32, transform: &ColorTransform) -> (u8, u8, u8) {
+    let (x, y, z) = lab_to_xyz(l, a, b);
+    let xyz = [x / 100.0, y / 100.0, z / 100.0];
+    let rgb_linear = mat_vec3(invert_mat3(transform.matrix), xyz);
+    transform.delinearize(rgb_linear[0], rgb_linear[1], rgb_linear[2])
+}
+
 // Helper functions
 
 fn gamma_to_linear(c: f32) -> f32 {
@@ -151,7 +929,7 @@ fn linear_to_gamma(c: f32) -> f32 {
     }
 }
 
-fn xyz_to_lab(x: f32, y: f32, z: f32) -> (f32, f32, f32) {
+pub(crate) fn xyz_to_lab(x: f32, y: f32, z: f32) -> (f32, f32, f32) {
     let fx = lab_f(x / D65_X);
     let fy = lab_f(y / D65_Y);
     let fz = lab_f(z / D65_Z);
@@ -246,4 +1024,240 @@ mod tests {
         let dist = color_distance_lab(l, a, b, l, a, b);
         assert!(dist < 0.001);
     }
+
+    #[test]
+    fn test_ciede2000_identical_colors_are_zero() {
+        let (l, a, b) = rgb_to_lab(128, 64, 192);
+        let dist = color_distance_ciede2000(l, a, b, l, a, b);
+        assert!(dist < 0.001);
+    }
+
+    #[test]
+    fn test_ciede2000_sharma_saturated_blue_pair() {
+        // Known reference case: L*=50,a*=2.6772,b*=-79.7751 vs L*=50,a*=0,b*=-82.7485
+        // from Sharma et al.'s CIEDE2000 test data, expected ΔE00 ≈ 2.0425.
+        let dist = color_distance_ciede2000(50.0, 2.6772, -79.7751, 50.0, 0.0, -82.7485);
+        assert!((dist - 2.0425).abs() < 0.01, "unexpected ΔE00: {}", dist);
+    }
+
+    #[test]
+    fn test_ciede2000_white_to_black_is_large() {
+        let (l1, a1, b1) = rgb_to_lab(255, 255, 255);
+        let (l2, a2, b2) = rgb_to_lab(0, 0, 0);
+        let dist = color_distance_ciede2000(l1, a1, b1, l2, a2, b2);
+        assert!(dist > 50.0);
+    }
+
+    #[test]
+    fn test_quantize_palette_rgba_basic_shape() {
+        let pixels = vec![
+            (255, 0, 0, 255), (255, 0, 0, 255),
+            (0, 255, 0, 255), (0, 255, 0, 255),
+            (0, 0, 255, 0), (0, 0, 255, 0),
+        ];
+        let result = quantize_palette_rgba(&pixels, 3, 10, PerceptualWeights::default());
+        assert_eq!(result.palette.len(), 3);
+        assert_eq!(result.indices.len(), pixels.len());
+        for &idx in &result.indices {
+            assert!(idx < 3);
+        }
+    }
+
+    #[test]
+    fn test_quantize_palette_rgba_separates_transparent_pixels() {
+        // Same RGB color, but half fully opaque and half fully transparent;
+        // with k=2 they should land in different clusters thanks to the
+        // alpha coordinate.
+        let mut pixels = vec![(100, 150, 200, 255); 20];
+        pixels.extend(vec![(100, 150, 200, 0); 20]);
+
+        let result = quantize_palette_rgba(&pixels, 2, 15, PerceptualWeights::default());
+        let opaque_cluster = result.indices[0];
+        let transparent_cluster = result.indices[39];
+        assert_ne!(opaque_cluster, transparent_cluster);
+    }
+
+    #[test]
+    fn test_quantize_palette_rgba_empty_input() {
+        let result = quantize_palette_rgba(&[], 3, 10, PerceptualWeights::default());
+        assert!(result.palette.is_empty());
+        assert!(result.indices.is_empty());
+    }
+
+    #[test]
+    fn test_color_transform_srgb_matches_rgb_to_lab() {
+        let transform = ColorTransform::for_working_space(WorkingSpace::Srgb);
+        for &(r, g, b) in &[(255, 0, 0), (0, 255, 0), (0, 0, 255), (128, 64, 192)] {
+            let (l1, a1, b1) = rgb_to_lab(r, g, b);
+            let (l2, a2, b2) = rgb_to_lab_with_profile(r, g, b, &transform);
+            assert!((l1 - l2).abs() < 0.01);
+            assert!((a1 - a2).abs() < 0.01);
+            assert!((b1 - b2).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn test_lab_to_rgb_with_profile_roundtrip() {
+        let transform = ColorTransform::for_working_space(WorkingSpace::AdobeRgb);
+        for &(r, g, b) in &[(255, 0, 0), (0, 255, 0), (0, 0, 255), (128, 64, 192)] {
+            let (l, a, b_lab) = rgb_to_lab_with_profile(r, g, b, &transform);
+            let (r2, g2, b2) = lab_to_rgb_with_profile(l, a, b_lab, &transform);
+            assert!((r as i32 - r2 as i32).abs() <= 2);
+            assert!((g as i32 - g2 as i32).abs() <= 2);
+            assert!((b as i32 - b2 as i32).abs() <= 2);
+        }
+    }
+
+    #[test]
+    fn test_color_transform_adobe_rgb_white_and_black() {
+        let transform = ColorTransform::for_working_space(WorkingSpace::AdobeRgb);
+        let (l_white, _, _) = rgb_to_lab_with_profile(255, 255, 255, &transform);
+        let (l_black, _, _) = rgb_to_lab_with_profile(0, 0, 0, &transform);
+        assert!((l_white - 100.0).abs() < 0.5);
+        assert!(l_black.abs() < 0.5);
+    }
+
+    /// Build a minimal "matrix/TRC" ICC profile byte blob with just the six
+    /// tags `from_icc_profile` reads (rXYZ/gXYZ/bXYZ colorants and
+    /// rTRC/gTRC/bTRC tone curves), each a pure-gamma-2.2 curve and the
+    /// sRGB/D50 colorant matrix, so parsing can be tested without shipping
+    /// a real vendor ICC file.
+    fn build_test_icc_profile() -> Vec<u8> {
+        fn xyz_tag(x: f32, y: f32, z: f32) -> Vec<u8> {
+            let mut out = Vec::new();
+            out.extend_from_slice(b"XYZ ");
+            out.extend_from_slice(&[0, 0, 0, 0]);
+            for v in [x, y, z] {
+                out.extend_from_slice(&((v * 65536.0).round() as i32).to_be_bytes());
+            }
+            out
+        }
+
+        fn gamma_curv_tag(gamma: f32) -> Vec<u8> {
+            let mut out = Vec::new();
+            out.extend_from_slice(b"curv");
+            out.extend_from_slice(&[0, 0, 0, 0]);
+            out.extend_from_slice(&1u32.to_be_bytes());
+            out.extend_from_slice(&((gamma * 256.0).round() as u16).to_be_bytes());
+            out
+        }
+
+        // D50-relative sRGB primaries (the values a real sRGB ICC profile ships).
+        let tag_data: Vec<(&[u8; 4], Vec<u8>)> = vec![
+            (b"rXYZ", xyz_tag(0.4360747, 0.2225045, 0.0139322)),
+            (b"gXYZ", xyz_tag(0.3850649, 0.7168786, 0.0971045)),
+            (b"bXYZ", xyz_tag(0.1430804, 0.0606169, 0.7141733)),
+            (b"rTRC", gamma_curv_tag(2.2)),
+            (b"gTRC", gamma_curv_tag(2.2)),
+            (b"bTRC", gamma_curv_tag(2.2)),
+        ];
+
+        let header = vec![0u8; 128];
+        let tag_count = tag_data.len() as u32;
+        let mut tag_table = Vec::new();
+        let mut payload = Vec::new();
+        let payload_start = 132 + tag_data.len() * 12;
+
+        for (signature, data) in &tag_data {
+            tag_table.extend_from_slice(*signature);
+            tag_table.extend_from_slice(&((payload_start + payload.len()) as u32).to_be_bytes());
+            tag_table.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            payload.extend_from_slice(data);
+        }
+
+        let mut profile = header;
+        profile.extend_from_slice(&tag_count.to_be_bytes());
+        profile.extend_from_slice(&tag_table);
+        profile.extend_from_slice(&payload);
+        profile
+    }
+
+    #[test]
+    fn test_from_icc_profile_parses_and_converts() {
+        let icc_bytes = build_test_icc_profile();
+        let transform = ColorTransform::from_icc_profile(&icc_bytes).expect("valid test ICC profile");
+
+        let (l_white, a_white, b_white) = rgb_to_lab_with_profile(255, 255, 255, &transform);
+        assert!((l_white - 100.0).abs() < 1.0);
+        assert!(a_white.abs() < 1.0);
+        assert!(b_white.abs() < 1.0);
+
+        let (l_black, _, _) = rgb_to_lab_with_profile(0, 0, 0, &transform);
+        assert!(l_black.abs() < 1.0);
+    }
+
+    #[test]
+    fn test_from_icc_profile_missing_tag_errors() {
+        let result = ColorTransform::from_icc_profile(&[0u8; 132]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_rgb_to_xyb_black_and_white() {
+        let (x, y, b) = rgb_to_xyb(0, 0, 0);
+        assert!(x.abs() < 0.001);
+        assert!(y.abs() < 0.001);
+        assert!(b.abs() < 0.001);
+
+        let (x, y, _b) = rgb_to_xyb(255, 255, 255);
+        assert!(x.abs() < 0.01); // neutral colors have no X chroma
+        assert!(y > 0.5); // white should have high Y luma
+    }
+
+    #[test]
+    fn test_xyb_roundtrip() {
+        let test_colors = vec![
+            (255, 0, 0),
+            (0, 255, 0),
+            (0, 0, 255),
+            (128, 128, 128),
+            (10, 200, 90),
+        ];
+
+        for (r, g, b) in test_colors {
+            let (x, y, b_xyb) = rgb_to_xyb(r, g, b);
+            let (r2, g2, b2) = xyb_to_rgb(x, y, b_xyb);
+
+            assert!((r as i32 - r2 as i32).abs() <= 2);
+            assert!((g as i32 - g2 as i32).abs() <= 2);
+            assert!((b as i32 - b2 as i32).abs() <= 2);
+        }
+    }
+
+    #[test]
+    fn test_kmeans_lab_xyb_space_produces_k_centroids() {
+        let pixels = vec![(255, 0, 0), (250, 5, 5), (0, 0, 255), (5, 5, 250)];
+        let palette = kmeans_lab(&pixels, 2, 10, None, DistanceMetric::Euclidean, ColorSpace::Xyb, None);
+        assert_eq!(palette.len(), 2);
+    }
+
+    #[test]
+    fn test_kmeans_lab_with_profile_produces_k_centroids() {
+        let pixels = vec![(255, 0, 0), (250, 5, 5), (0, 0, 255), (5, 5, 250)];
+        let transform = ColorTransform::for_working_space(WorkingSpace::AdobeRgb);
+        let palette = kmeans_lab(&pixels, 2, 10, None, DistanceMetric::Euclidean, ColorSpace::Lab, Some(&transform));
+        assert_eq!(palette.len(), 2);
+    }
+
+    #[test]
+    fn test_kmeans_lab_mask_prevents_outlier_centroid_pull() {
+        // 10 red pixels, 10 green pixels, and 10 far-outlier blue pixels
+        // that are masked out. Left unmasked, the blue outliers would be
+        // assigned to one of the two clusters and drag its centroid toward
+        // blue; masking them out should keep the 2 centroids on red and
+        // green.
+        let mut pixels = vec![(200u8, 20u8, 20u8); 10];
+        pixels.extend(vec![(20, 200, 20); 10]);
+        pixels.extend(vec![(20, 20, 200); 10]);
+        let mask: Vec<bool> = (0..30).map(|i| i < 20).collect();
+
+        let palette = kmeans_lab(&pixels, 2, 10, Some(&mask), DistanceMetric::Euclidean, ColorSpace::Lab, None);
+        assert_eq!(palette.len(), 2);
+
+        // Every masked-out (blue) pixel still gets assigned a centroid, but
+        // neither centroid should have been pulled toward blue.
+        for &(r, g, b) in &palette {
+            assert!(!(b as i32 - r as i32 > 50 && b as i32 - g as i32 > 50), "centroid {:?} looks blue-shifted", (r, g, b));
+        }
+    }
 }
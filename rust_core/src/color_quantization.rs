@@ -2,40 +2,146 @@ use pyo3::exceptions::PyValueError;
 use image::GenericImageView;
 use pyo3::prelude::*;
 use rayon::prelude::*;
+use png::BitDepth;
 
-pub fn quantize(image_bytes: &[u8], k: usize, max_iter: usize) -> PyResult<Vec<u8>> {
+pub fn quantize(
+    image_bytes: &[u8],
+    k: usize,
+    max_iter: usize,
+    ignore_transparent: bool,
+    alpha_threshold: u8
+) -> PyResult<Vec<u8>> {
     let img = image::load_from_memory(image_bytes)
         .map_err(|e| PyValueError::new_err(e.to_string()))?;
-    
+
     let rgba = img.to_rgba8();
     let (w, h) = img.dimensions();
-    let raw_pixels = rgba.as_raw();
-    
-    let pixels: Vec<[f32; 3]> = (0..raw_pixels.len())
-        .step_by(4)
-        .map(|i| [raw_pixels[i] as f32, raw_pixels[i+1] as f32, raw_pixels[i+2] as f32])
-        .collect();
+    let pixels = extract_rgb_pixels(&rgba);
+
+    if k == 0 || pixels.is_empty() {
+        return Err(PyValueError::new_err("Invalid k or empty image"));
+    }
+
+    let mask: Vec<bool> = if ignore_transparent {
+        rgba.pixels().map(|p| p[3] >= alpha_threshold).collect()
+    } else {
+        vec![true; pixels.len()]
+    };
+
+    let (centroids, assignments) = kmeans_rgb(&pixels, &mask, k, max_iter);
+
+    let mut output = Vec::with_capacity((w * h * 4) as usize);
+    for (i, &idx) in assignments.iter().enumerate() {
+        if ignore_transparent && !mask[i] {
+            output.extend_from_slice(&[0, 0, 0, 0]);
+            continue;
+        }
+
+        let c = centroids[idx];
+        output.push(c[0].round() as u8);
+        output.push(c[1].round() as u8);
+        output.push(c[2].round() as u8);
+        output.push(255);
+    }
+
+    let img_buf = image::ImageBuffer::<image::Rgba<u8>, _>::from_raw(w, h, output)
+        .ok_or_else(|| PyValueError::new_err("Failed to create image buffer"))?;
+
+    let mut png_data = Vec::new();
+    image::DynamicImage::ImageRgba8(img_buf)
+        .write_to(&mut std::io::Cursor::new(&mut png_data), image::ImageFormat::Png)
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+
+    Ok(png_data)
+}
+
+/// Same k-means quantization as `quantize`, but emits a palette-indexed PNG
+/// (PLTE palette + per-pixel indices) instead of expanding back to truecolor.
+/// Bit depth is chosen from `k` the way standard indexed PNGs do.
+pub fn quantize_colors_indexed(image_bytes: &[u8], k: usize, max_iter: usize) -> PyResult<Vec<u8>> {
+    let img = image::load_from_memory(image_bytes)
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+
+    let rgba = img.to_rgba8();
+    let (w, h) = img.dimensions();
+    let pixels = extract_rgb_pixels(&rgba);
 
     if k == 0 || pixels.is_empty() {
         return Err(PyValueError::new_err("Invalid k or empty image"));
     }
+    if k > 256 {
+        return Err(PyValueError::new_err("k must be at most 256 for indexed PNG output"));
+    }
+
+    let mask = vec![true; pixels.len()];
+    let (centroids, assignments) = kmeans_rgb(&pixels, &mask, k, max_iter);
 
-    let step = (pixels.len() / k).max(1);
-    let mut centroids: Vec<[f32; 3]> = pixels.iter().step_by(step).take(k).cloned().collect();
+    let bit_depth = match k {
+        n if n <= 2 => BitDepth::One,
+        n if n <= 4 => BitDepth::Two,
+        n if n <= 16 => BitDepth::Four,
+        _ => BitDepth::Eight,
+    };
+
+    let palette: Vec<u8> = centroids
+        .iter()
+        .flat_map(|c| [c[0].round() as u8, c[1].round() as u8, c[2].round() as u8])
+        .collect();
+
+    let indexed_rows = pack_indices(&assignments, w as usize, h as usize, bit_depth);
+
+    let mut png_data = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut png_data, w, h);
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(bit_depth);
+        encoder.set_palette(palette);
+
+        let mut writer = encoder
+            .write_header()
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        writer
+            .write_image_data(&indexed_rows)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+    }
+
+    Ok(png_data)
+}
+
+fn extract_rgb_pixels(rgba: &image::RgbaImage) -> Vec<[f32; 3]> {
+    let raw_pixels = rgba.as_raw();
+    (0..raw_pixels.len())
+        .step_by(4)
+        .map(|i| [raw_pixels[i] as f32, raw_pixels[i + 1] as f32, raw_pixels[i + 2] as f32])
+        .collect()
+}
+
+/// Plain k-means over RGB pixels. Returns the final centroids and each
+/// pixel's assigned centroid index.
+///
+/// `mask` marks which pixels participate in seeding and centroid updates;
+/// masked-out pixels (e.g. below an alpha threshold) still get a nearest
+/// assignment but never pull the centroids toward them.
+fn kmeans_rgb(pixels: &[[f32; 3]], mask: &[bool], k: usize, max_iter: usize) -> (Vec<[f32; 3]>, Vec<usize>) {
+    let active_pixels: Vec<[f32; 3]> = pixels.iter().zip(mask).filter(|(_, &m)| m).map(|(&p, _)| p).collect();
+    let seed_source: &[[f32; 3]] = if active_pixels.is_empty() { pixels } else { &active_pixels };
+
+    let step = (seed_source.len() / k).max(1);
+    let mut centroids: Vec<[f32; 3]> = seed_source.iter().step_by(step).take(k).cloned().collect();
     if centroids.len() < k {
-        centroids.resize(k, pixels[0]);
+        centroids.resize(k, seed_source[0]);
     }
 
     let mut assignments = vec![0usize; pixels.len()];
-    
+
     for _ in 0..max_iter {
         assignments.par_iter_mut().enumerate().for_each(|(i, a)| {
             let p = pixels[i];
             let mut best_idx = 0;
             let mut min_dist = f32::MAX;
-            
+
             for (ci, c) in centroids.iter().enumerate() {
-                let dist = (p[0]-c[0]).powi(2) + (p[1]-c[1]).powi(2) + (p[2]-c[2]).powi(2);
+                let dist = (p[0] - c[0]).powi(2) + (p[1] - c[1]).powi(2) + (p[2] - c[2]).powi(2);
                 if dist < min_dist {
                     min_dist = dist;
                     best_idx = ci;
@@ -46,38 +152,86 @@ pub fn quantize(image_bytes: &[u8], k: usize, max_iter: usize) -> PyResult<Vec<u
 
         let mut sums = vec![[0f32; 3]; k];
         let mut counts = vec![0u32; k];
-        
-        for (idx, pixel) in assignments.iter().zip(&pixels) {
+
+        for ((idx, pixel), &included) in assignments.iter().zip(pixels).zip(mask) {
+            if !included {
+                continue;
+            }
             sums[*idx][0] += pixel[0];
             sums[*idx][1] += pixel[1];
             sums[*idx][2] += pixel[2];
             counts[*idx] += 1;
         }
-        
+
         for i in 0..k {
             if counts[i] > 0 {
                 let c = counts[i] as f32;
-                centroids[i] = [sums[i][0]/c, sums[i][1]/c, sums[i][2]/c];
+                centroids[i] = [sums[i][0] / c, sums[i][1] / c, sums[i][2] / c];
             }
         }
     }
 
-    let mut output = Vec::with_capacity((w * h * 4) as usize);
-    for idx in assignments {
-        let c = centroids[idx];
-        output.push(c[0].round() as u8);
-        output.push(c[1].round() as u8);
-        output.push(c[2].round() as u8);
-        output.push(255);
+    (centroids, assignments)
+}
+
+/// Pack per-pixel palette indices into PNG scanlines at the given bit depth
+/// (1/2/4/8 bits per pixel, MSB-first, each row padded to a byte boundary).
+fn pack_indices(assignments: &[usize], width: usize, height: usize, depth: BitDepth) -> Vec<u8> {
+    let bits_per_pixel = match depth {
+        BitDepth::One => 1,
+        BitDepth::Two => 2,
+        BitDepth::Four => 4,
+        _ => 8,
+    };
+
+    let bytes_per_row = (width * bits_per_pixel + 7) / 8;
+    let mut out = vec![0u8; bytes_per_row * height];
+
+    for y in 0..height {
+        let row = &mut out[y * bytes_per_row..(y + 1) * bytes_per_row];
+        for x in 0..width {
+            let idx = assignments[y * width + x] as u8;
+            if bits_per_pixel == 8 {
+                row[x] = idx;
+            } else {
+                let pixels_per_byte = 8 / bits_per_pixel;
+                let byte_idx = x / pixels_per_byte;
+                let shift = 8 - bits_per_pixel * (x % pixels_per_byte + 1);
+                row[byte_idx] |= idx << shift;
+            }
+        }
     }
 
-    let img_buf = image::ImageBuffer::<image::Rgba<u8>, _>::from_raw(w, h, output)
-        .ok_or_else(|| PyValueError::new_err("Failed to create image buffer"))?;
-    
-    let mut png_data = Vec::new();
-    image::DynamicImage::ImageRgba8(img_buf)
-        .write_to(&mut std::io::Cursor::new(&mut png_data), image::ImageFormat::Png)
-        .map_err(|e| PyValueError::new_err(e.to_string()))?;
-    
-    Ok(png_data)
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kmeans_rgb_mask_prevents_outlier_centroid_pull() {
+        // 10 red pixels, 10 green pixels, and 10 far-outlier blue pixels
+        // that are masked out. Left unmasked, the blue outliers would be
+        // assigned to one of the 2 clusters and drag its centroid toward
+        // blue; masking them out should let red and green split cleanly.
+        let mut pixels = vec![[200.0f32, 20.0, 20.0]; 10];
+        pixels.extend(vec![[20.0, 200.0, 20.0]; 10]);
+        pixels.extend(vec![[20.0, 20.0, 200.0]; 10]);
+        let mask: Vec<bool> = (0..30).map(|i| i < 20).collect();
+
+        let (centroids, assignments) = kmeans_rgb(&pixels, &mask, 2, 10);
+
+        assert_eq!(assignments.len(), 30);
+        let red_idx = assignments[0];
+        let green_idx = assignments[10];
+        assert_ne!(red_idx, green_idx, "masking out the blue outliers should let red and green form separate clusters");
+        assert!(assignments[0..10].iter().all(|&a| a == red_idx));
+        assert!(assignments[10..20].iter().all(|&a| a == green_idx));
+
+        // Neither centroid should have been pulled toward the masked blue.
+        for c in &centroids {
+            assert!(!(c[2] - c[0] > 50.0 && c[2] - c[1] > 50.0), "centroid {:?} looks blue-shifted", c);
+        }
+    }
 }
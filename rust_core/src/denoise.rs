@@ -0,0 +1,114 @@
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use image::{DynamicImage, GenericImageView};
+use ndarray::Array4;
+
+use crate::model_loader;
+
+/// DnCNN-style residual image denoising.
+///
+/// The network is a residual learner: it does not predict the clean image,
+/// it predicts the noise `R(y)` present in the noisy input `y`, so the clean
+/// result is recovered as `x = y - R(y)`.
+
+/// Run the denoiser and blend the predicted residual back in at `strength`
+/// (0.0 = untouched input, 1.0 = full correction).
+pub fn denoise_image(image_bytes: &[u8], model_path: &str, strength: f32) -> PyResult<Vec<u8>> {
+    let img = image::load_from_memory(image_bytes)
+        .map_err(|e| PyValueError::new_err(format!("Failed to load image: {}", e)))?;
+
+    let rgb = img.to_rgb8();
+    let (width, height) = img.dimensions();
+    let strength = strength.clamp(0.0, 1.0);
+
+    // Normalize to [0, 1] and arrange as NCHW (1, 3, H, W)
+    let mut input = Array4::<f32>::zeros((1, 3, height as usize, width as usize));
+    for (x, y, pixel) in rgb.enumerate_pixels() {
+        for c in 0..3 {
+            input[[0, c, y as usize, x as usize]] = pixel[c] as f32 / 255.0;
+        }
+    }
+
+    let residual = model_loader::run_inference(model_path, input, "input", "output")?;
+
+    let plane = (width as usize) * (height as usize);
+    validate_residual_len(&residual, width, height)?;
+
+    let mut out = image::RgbImage::new(width, height);
+    for (x, y, pixel) in rgb.enumerate_pixels() {
+        let idx = (y as usize) * (width as usize) + (x as usize);
+        let mut channels = [0u8; 3];
+        for c in 0..3 {
+            let noisy = pixel[c] as f32 / 255.0;
+            let noise = residual[c * plane + idx];
+            channels[c] = (apply_residual(noisy, noise, strength) * 255.0).round() as u8;
+        }
+        out.put_pixel(x, y, image::Rgb(channels));
+    }
+
+    let mut png_data = Vec::new();
+    DynamicImage::ImageRgb8(out)
+        .write_to(&mut std::io::Cursor::new(&mut png_data), image::ImageFormat::Png)
+        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+
+    Ok(png_data)
+}
+
+/// Blend a predicted noise residual back into a normalized (`[0, 1]`) pixel
+/// value: `denoised = noisy - noise * strength`, clamped back into range in
+/// case the residual overshoots at the input's extremes.
+fn apply_residual(noisy: f32, noise: f32, strength: f32) -> f32 {
+    (noisy - noise * strength).clamp(0.0, 1.0)
+}
+
+/// Check that a flattened `(1, 3, H, W)` residual tensor has the length the
+/// image's dimensions imply.
+fn validate_residual_len(residual: &[f32], width: u32, height: u32) -> PyResult<()> {
+    let expected_len = 3 * (width as usize) * (height as usize);
+    if residual.len() != expected_len {
+        return Err(PyRuntimeError::new_err(format!(
+            "Unexpected denoiser output size: got {}, expected {}",
+            residual.len(),
+            expected_len
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_residual_zero_strength_is_untouched() {
+        assert!((apply_residual(0.6, 0.4, 0.0) - 0.6).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_apply_residual_full_strength_subtracts_noise() {
+        assert!((apply_residual(0.6, 0.4, 1.0) - 0.2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_apply_residual_half_strength() {
+        assert!((apply_residual(0.6, 0.4, 0.5) - 0.4).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_apply_residual_clamps_out_of_range() {
+        assert_eq!(apply_residual(0.1, 0.8, 1.0), 0.0);
+        assert_eq!(apply_residual(0.9, -0.8, 1.0), 1.0);
+    }
+
+    #[test]
+    fn test_validate_residual_len_accepts_correct_size() {
+        let residual = vec![0.0f32; 3 * 4 * 5];
+        assert!(validate_residual_len(&residual, 4, 5).is_ok());
+    }
+
+    #[test]
+    fn test_validate_residual_len_rejects_wrong_size() {
+        let residual = vec![0.0f32; 10];
+        assert!(validate_residual_len(&residual, 4, 5).is_err());
+    }
+}
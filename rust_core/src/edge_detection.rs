@@ -3,49 +3,180 @@ use pyo3::prelude::*;
 use rayon::prelude::*;
 use image::GenericImageView;
 
-pub fn sobel_edge_detection(image_bytes: &[u8], threshold: u8) -> PyResult<Vec<u8>> {
+/// Which channel the Sobel gradient is measured on.
+///
+/// `None`/`"luma"` preserves the original plain-grayscale behavior. `"lab"`
+/// and `"xyb"` instead measure gradients on that space's luma-like channel
+/// (LAB's L*, XYB's Y), which can pick up edges that are a pure hue shift
+/// with little luma contrast — the kind naive grayscale Sobel misses.
+fn luma_channel_for_space(img: &image::DynamicImage, color_space: Option<&str>) -> PyResult<Vec<u8>> {
+    match color_space {
+        None | Some("luma") => Ok(img.to_luma8().into_raw()),
+        Some("lab") => {
+            let rgb = img.to_rgb8();
+            Ok(rgb.pixels()
+                .map(|p| {
+                    let (l, _, _) = crate::color_lab::rgb_to_lab(p[0], p[1], p[2]);
+                    (l * 2.55).round().clamp(0.0, 255.0) as u8
+                })
+                .collect())
+        }
+        Some("xyb") => {
+            let rgb = img.to_rgb8();
+            Ok(rgb.pixels()
+                .map(|p| {
+                    let (_, y, _) = crate::color_lab::rgb_to_xyb(p[0], p[1], p[2]);
+                    (y * 301.5).round().clamp(0.0, 255.0) as u8
+                })
+                .collect())
+        }
+        Some(other) => Err(PyValueError::new_err(format!(
+            "Unknown color_space '{}': expected 'luma', 'lab', or 'xyb'", other
+        ))),
+    }
+}
+
+pub fn sobel_edge_detection(
+    image_bytes: &[u8],
+    threshold: u8,
+    ignore_transparent: bool,
+    alpha_threshold: u8,
+    color_space: Option<&str>
+) -> PyResult<Vec<u8>> {
     let img = image::load_from_memory(image_bytes)
         .map_err(|e| PyValueError::new_err(e.to_string()))?;
-    
-    let gray = img.to_luma8();
+
+    let gray = luma_channel_for_space(&img, color_space)?;
     let (w, h) = img.dimensions();
-    
+
     let sobel_x = [[-1i32, 0, 1], [-2, 0, 2], [-1, 0, 1]];
     let sobel_y = [[-1i32, -2, -1], [0, 0, 0], [1, 2, 1]];
-    
+
+    let mask: Option<Vec<bool>> = if ignore_transparent {
+        let rgba = img.to_rgba8();
+        Some(rgba.pixels().map(|p| p[3] >= alpha_threshold).collect())
+    } else {
+        None
+    };
+
     let mut edges = vec![0u8; (w * h) as usize];
-    
+
     edges.par_chunks_mut(w as usize).enumerate().for_each(|(y, row)| {
         if y == 0 || y >= (h as usize - 1) { return; }
-        
+
         for x in 1..(w as usize - 1) {
+            if let Some(m) = &mask {
+                if !m[y * w as usize + x] {
+                    row[x] = 0;
+                    continue;
+                }
+            }
+
             let mut gx = 0i32;
             let mut gy = 0i32;
-            
+
             for ky in 0..3 {
                 for kx in 0..3 {
-                    let px = gray.get_pixel((x + kx - 1) as u32, (y + ky - 1) as u32)[0] as i32;
+                    let px = gray[(y + ky - 1) * w as usize + (x + kx - 1)] as i32;
                     gx += px * sobel_x[ky][kx];
                     gy += px * sobel_y[ky][kx];
                 }
             }
-            
+
             let magnitude = ((gx * gx + gy * gy) as f32).sqrt() as u8;
             row[x] = if magnitude > threshold { 255 } else { 0 };
         }
     });
-    
+
+    if let Some(mask) = mask {
+        // Preserve transparency: pixels excluded by the alpha mask are
+        // forced to a zero edge value and stay transparent in the output.
+        let mut luma_alpha = Vec::with_capacity((w * h * 2) as usize);
+        for (&edge_value, &included) in edges.iter().zip(&mask) {
+            luma_alpha.push(edge_value);
+            luma_alpha.push(if included { 255 } else { 0 });
+        }
+
+        let edge_img = image::ImageBuffer::<image::LumaA<u8>, _>::from_raw(w, h, luma_alpha)
+            .ok_or_else(|| PyValueError::new_err("Failed to create edge image"))?;
+
+        let mut png_data = Vec::new();
+        image::DynamicImage::ImageLumaA8(edge_img)
+            .write_to(&mut std::io::Cursor::new(&mut png_data), image::ImageFormat::Png)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+
+        return Ok(png_data);
+    }
+
     let edge_img = image::ImageBuffer::from_raw(w, h, edges)
         .ok_or_else(|| PyValueError::new_err("Failed to create edge image"))?;
-    
+
     let mut png_data = Vec::new();
     image::DynamicImage::ImageLuma8(edge_img)
         .write_to(&mut std::io::Cursor::new(&mut png_data), image::ImageFormat::Png)
         .map_err(|e| PyValueError::new_err(e.to_string()))?;
-    
+
     Ok(png_data)
 }
 
 pub fn canny_edge_detection(_image_bytes: &[u8], _low: u8, _high: u8) -> PyResult<Vec<u8>> {
     Err(PyValueError::new_err("Canny not implemented yet - use Sobel"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn encode_test_png(w: u32, h: u32, pixel: impl Fn(u32, u32) -> Rgba<u8>) -> Vec<u8> {
+        let mut img = image::RgbaImage::new(w, h);
+        for y in 0..h {
+            for x in 0..w {
+                img.put_pixel(x, y, pixel(x, y));
+            }
+        }
+
+        let mut png_data = Vec::new();
+        image::DynamicImage::ImageRgba8(img)
+            .write_to(&mut std::io::Cursor::new(&mut png_data), image::ImageFormat::Png)
+            .unwrap();
+        png_data
+    }
+
+    #[test]
+    fn test_sobel_edge_detection_mask_forces_transparent_pixels_to_zero_edge() {
+        // Left half is a solid block, right half is a sharp edge (black to
+        // white) but marked transparent via alpha. With ignore_transparent,
+        // the masked-out edge must be forced to zero regardless of the
+        // underlying gradient.
+        let png = encode_test_png(8, 8, |x, _y| {
+            if x < 4 {
+                Rgba([0, 0, 0, 255])
+            } else if x == 4 {
+                Rgba([255, 255, 255, 0])
+            } else {
+                Rgba([255, 255, 255, 0])
+            }
+        });
+
+        let out = sobel_edge_detection(&png, 32, true, 10, None).unwrap();
+        let decoded = image::load_from_memory(&out).unwrap().to_luma_alpha8();
+
+        for p in decoded.pixels() {
+            if p[1] == 0 {
+                assert_eq!(p[0], 0, "masked-out pixel must have a zero edge value");
+            }
+        }
+    }
+
+    #[test]
+    fn test_sobel_edge_detection_without_mask_has_full_opacity() {
+        let png = encode_test_png(8, 8, |x, _y| {
+            if x < 4 { Rgba([0, 0, 0, 255]) } else { Rgba([255, 255, 255, 255]) }
+        });
+
+        let out = sobel_edge_detection(&png, 32, false, 0, None).unwrap();
+        let decoded = image::load_from_memory(&out).unwrap();
+        assert!(matches!(decoded, image::DynamicImage::ImageLuma8(_)));
+    }
+}
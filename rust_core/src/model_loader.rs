@@ -1,50 +1,114 @@
 use pyo3::exceptions::PyRuntimeError;
 use pyo3::prelude::*;
 use std::path::PathBuf;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use std::collections::HashMap;
 
-/// Model cache to avoid reloading models on every inference
-/// Note: Using String keys for paths instead of Arc<Session> for now
-/// Full ONNX integration will be completed in Week 6
-static MODEL_CACHE: Mutex<Option<HashMap<String, bool>>> = Mutex::new(None);
+use ndarray::Array4;
+use ort::{Environment, Session, SessionBuilder, Value};
+use ort::tensor::OrtOwnedTensor;
+
+/// Model cache so repeated calls against the same `.onnx` path reuse one loaded
+/// session instead of paying the load/compile cost on every inference.
+static MODEL_CACHE: Mutex<Option<HashMap<String, Arc<Session>>>> = Mutex::new(None);
+
+/// Shared ONNX Runtime environment that every `Session` is built against.
+static ONNX_ENVIRONMENT: Mutex<Option<Arc<Environment>>> = Mutex::new(None);
+
+fn get_or_init_environment() -> PyResult<Arc<Environment>> {
+    let mut env = ONNX_ENVIRONMENT.lock().unwrap();
+    if env.is_none() {
+        let environment = Environment::builder()
+            .with_name("vectorization_four_stages")
+            .build()
+            .map_err(|e| PyRuntimeError::new_err(format!("Failed to init ONNX Runtime: {}", e)))?;
+        *env = Some(Arc::new(environment));
+    }
+
+    Ok(env.as_ref().unwrap().clone())
+}
 
 /// Initialize the ONNX Runtime environment
 pub fn init_onnx_runtime() -> PyResult<()> {
-    // ONNX Runtime 1.16 initializes automatically
-    // Just initialize our cache
+    get_or_init_environment()?;
+
     let mut cache = MODEL_CACHE.lock().unwrap();
     if cache.is_none() {
         *cache = Some(HashMap::new());
     }
-    
+
     Ok(())
 }
 
-/// Load ONNX model with caching (stub for Week 6 implementation)
-pub fn load_model(model_path: &str) -> PyResult<bool> {
-    // Check cache first
+/// Load an ONNX model, memoizing the built `Session` by path so later calls
+/// against the same model reuse it instead of reloading and recompiling.
+pub fn load_model(model_path: &str) -> PyResult<Arc<Session>> {
     let mut cache = MODEL_CACHE.lock().unwrap();
-    let cache_map = cache.as_mut().unwrap();
-    
-    if let Some(&cached) = cache_map.get(model_path) {
-        return Ok(cached);
+    let cache_map = cache.get_or_insert_with(HashMap::new);
+
+    if let Some(session) = cache_map.get(model_path) {
+        return Ok(session.clone());
     }
-    
-    // Check if model file exists
+
     let path = PathBuf::from(model_path);
-    
     if !path.exists() {
         return Err(PyRuntimeError::new_err(format!(
-            "Model file not found: {}. Please download the model first.", 
+            "Model file not found: {}. Please download the model first.",
             model_path
         )));
     }
-    
-    // Mark as cached (actual loading will be in Week 6)
-    cache_map.insert(model_path.to_string(), true);
-    
-    Ok(true)
+    drop(cache);
+
+    let environment = get_or_init_environment()?;
+    let session = SessionBuilder::new(&environment)
+        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?
+        .with_model_from_file(&path)
+        .map_err(|e| {
+            PyRuntimeError::new_err(format!("Failed to load model {}: {}", model_path, e))
+        })?;
+    let session = Arc::new(session);
+
+    let mut cache = MODEL_CACHE.lock().unwrap();
+    let cache_map = cache.get_or_insert_with(HashMap::new);
+    cache_map.insert(model_path.to_string(), session.clone());
+
+    Ok(session)
+}
+
+/// Run inference on a loaded (or freshly loaded) model.
+///
+/// `input` must already be arranged as an NCHW `f32` tensor. Returns the
+/// flattened output tensor in row-major order.
+pub fn run_inference(
+    model_path: &str,
+    input: Array4<f32>,
+    input_name: &str,
+    output_name: &str,
+) -> PyResult<Vec<f32>> {
+    let session = load_model(model_path)?;
+
+    let input_value = Value::from_array(session.allocator(), &input.into_dyn())
+        .map_err(|e| {
+            PyRuntimeError::new_err(format!("Failed to bind input '{}': {}", input_name, e))
+        })?;
+
+    let outputs = session
+        .run(vec![input_value])
+        .map_err(|e| PyRuntimeError::new_err(format!("Inference failed: {}", e)))?;
+
+    let output_index = session
+        .outputs
+        .iter()
+        .position(|o| o.name == output_name)
+        .unwrap_or(0);
+
+    let output: OrtOwnedTensor<f32, _> = outputs[output_index]
+        .try_extract()
+        .map_err(|e| {
+            PyRuntimeError::new_err(format!("Failed to read output '{}': {}", output_name, e))
+        })?;
+
+    Ok(output.view().iter().copied().collect())
 }
 
 /// Check if model exists
@@ -56,12 +120,12 @@ pub fn model_exists(model_path: &str) -> bool {
 pub fn get_model_version(model_path: &str) -> Option<String> {
     let path = PathBuf::from(model_path);
     let filename = path.file_stem()?.to_str()?;
-    
+
     // Extract version from filename pattern: model_name_v1.2.3.onnx
     if let Some(version_start) = filename.rfind("_v") {
         return Some(filename[version_start + 2..].to_string());
     }
-    
+
     None
 }
 
@@ -0,0 +1,310 @@
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use image::{GenericImageView, RgbaImage};
+
+/// Quadrilateral detection and perspective rectification.
+///
+/// Finds the dominant four-sided region in a photographed document/sign and
+/// warps it to an axis-aligned rectangle so downstream quantization and
+/// segmentation see a clean, fronto-parallel image.
+
+/// Detect the document boundary and warp it to an `out_w` x `out_h` rectangle.
+pub fn rectify_document(image_bytes: &[u8], out_w: u32, out_h: u32) -> PyResult<Vec<u8>> {
+    if out_w == 0 || out_h == 0 {
+        return Err(PyValueError::new_err("out_w and out_h must both be greater than 0"));
+    }
+
+    let img = image::load_from_memory(image_bytes)
+        .map_err(|e| PyValueError::new_err(format!("Failed to load image: {}", e)))?;
+    let rgba = img.to_rgba8();
+
+    let edge_png = crate::edge_detection::sobel_edge_detection(image_bytes, 64, false, 0, None)?;
+    let edge_img = image::load_from_memory(&edge_png)
+        .map_err(|e| PyValueError::new_err(format!("Failed to decode edge map: {}", e)))?
+        .to_luma8();
+
+    let edge_points: Vec<(f32, f32)> = edge_img
+        .enumerate_pixels()
+        .filter(|(_, _, p)| p[0] > 128)
+        .map(|(x, y, _)| (x as f32, y as f32))
+        .collect();
+
+    if edge_points.len() < 4 {
+        return Err(PyValueError::new_err(
+            "Not enough edge points to find a document boundary",
+        ));
+    }
+
+    let hull = convex_hull(&edge_points);
+    let quad = simplify_to_quad(hull)
+        .ok_or_else(|| PyValueError::new_err("Could not find a four-sided region"))?;
+    let corners = order_corners(quad);
+
+    let dst = [
+        (0.0, 0.0),
+        ((out_w - 1) as f32, 0.0),
+        ((out_w - 1) as f32, (out_h - 1) as f32),
+        (0.0, (out_h - 1) as f32),
+    ];
+
+    let homography = solve_homography(corners, dst);
+    let inverse = invert_3x3(homography);
+
+    let mut out_img = RgbaImage::new(out_w, out_h);
+    for y in 0..out_h {
+        for x in 0..out_w {
+            let (sx, sy) = apply_homography(&inverse, x as f32, y as f32);
+            let pixel = bilinear_sample(&rgba, sx, sy);
+            out_img.put_pixel(x, y, image::Rgba(pixel));
+        }
+    }
+
+    let mut png_data = Vec::new();
+    image::DynamicImage::ImageRgba8(out_img)
+        .write_to(&mut std::io::Cursor::new(&mut png_data), image::ImageFormat::Png)
+        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+
+    Ok(png_data)
+}
+
+fn cross(o: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+    (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+}
+
+/// Convex hull via Andrew's monotone chain.
+fn convex_hull(points: &[(f32, f32)]) -> Vec<(f32, f32)> {
+    let mut pts = points.to_vec();
+    pts.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap().then(a.1.partial_cmp(&b.1).unwrap()));
+    pts.dedup_by(|a, b| a.0 == b.0 && a.1 == b.1);
+
+    if pts.len() < 3 {
+        return pts;
+    }
+
+    let mut lower: Vec<(f32, f32)> = Vec::new();
+    for &p in &pts {
+        while lower.len() >= 2 && cross(lower[lower.len() - 2], lower[lower.len() - 1], p) <= 0.0 {
+            lower.pop();
+        }
+        lower.push(p);
+    }
+
+    let mut upper: Vec<(f32, f32)> = Vec::new();
+    for &p in pts.iter().rev() {
+        while upper.len() >= 2 && cross(upper[upper.len() - 2], upper[upper.len() - 1], p) <= 0.0 {
+            upper.pop();
+        }
+        upper.push(p);
+    }
+
+    lower.pop();
+    upper.pop();
+    lower.extend(upper);
+    lower
+}
+
+fn triangle_area(a: (f32, f32), b: (f32, f32), c: (f32, f32)) -> f32 {
+    ((b.0 - a.0) * (c.1 - a.1) - (c.0 - a.0) * (b.1 - a.1)).abs() / 2.0
+}
+
+/// Collapse a convex hull to a quadrilateral by repeatedly dropping the
+/// vertex whose removal loses the least enclosed area. This greedy choice
+/// does not guarantee the largest-area quadrilateral inside the hull.
+fn simplify_to_quad(mut hull: Vec<(f32, f32)>) -> Option<[(f32, f32); 4]> {
+    if hull.len() < 4 {
+        return None;
+    }
+
+    while hull.len() > 4 {
+        let n = hull.len();
+        let mut min_area = f32::MAX;
+        let mut min_idx = 0;
+
+        for i in 0..n {
+            let prev = hull[(i + n - 1) % n];
+            let cur = hull[i];
+            let next = hull[(i + 1) % n];
+            let area = triangle_area(prev, cur, next);
+            if area < min_area {
+                min_area = area;
+                min_idx = i;
+            }
+        }
+
+        hull.remove(min_idx);
+    }
+
+    Some([hull[0], hull[1], hull[2], hull[3]])
+}
+
+/// Order four corners as (top-left, top-right, bottom-right, bottom-left).
+fn order_corners(pts: [(f32, f32); 4]) -> [(f32, f32); 4] {
+    let mut by_sum = pts;
+    by_sum.sort_by(|a, b| (a.0 + a.1).partial_cmp(&(b.0 + b.1)).unwrap());
+    let top_left = by_sum[0];
+    let bottom_right = by_sum[3];
+
+    let mut by_diff = pts;
+    by_diff.sort_by(|a, b| (a.1 - a.0).partial_cmp(&(b.1 - b.0)).unwrap());
+    let top_right = by_diff[0];
+    let bottom_left = by_diff[3];
+
+    [top_left, top_right, bottom_right, bottom_left]
+}
+
+/// Solve the 3x3 homography `H` (with `h33 = 1`) mapping `src` to `dst` via
+/// the 8-unknown DLT system built from the 4 point correspondences.
+fn solve_homography(src: [(f32, f32); 4], dst: [(f32, f32); 4]) -> [[f32; 3]; 3] {
+    let mut a = [[0f32; 8]; 8];
+    let mut b = [0f32; 8];
+
+    for i in 0..4 {
+        let (x, y) = src[i];
+        let (xp, yp) = dst[i];
+        let row0 = 2 * i;
+        let row1 = 2 * i + 1;
+
+        a[row0] = [x, y, 1.0, 0.0, 0.0, 0.0, -x * xp, -y * xp];
+        b[row0] = xp;
+
+        a[row1] = [0.0, 0.0, 0.0, x, y, 1.0, -x * yp, -y * yp];
+        b[row1] = yp;
+    }
+
+    let h = solve_linear_system(a, b);
+    [[h[0], h[1], h[2]], [h[3], h[4], h[5]], [h[6], h[7], 1.0]]
+}
+
+/// Gaussian elimination with partial pivoting for an 8x8 system.
+fn solve_linear_system(mut a: [[f32; 8]; 8], mut b: [f32; 8]) -> [f32; 8] {
+    for col in 0..8 {
+        let mut pivot = col;
+        for row in (col + 1)..8 {
+            if a[row][col].abs() > a[pivot][col].abs() {
+                pivot = row;
+            }
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+
+        let divisor = a[col][col];
+        for k in col..8 {
+            a[col][k] /= divisor;
+        }
+        b[col] /= divisor;
+
+        for row in 0..8 {
+            if row != col {
+                let factor = a[row][col];
+                for k in col..8 {
+                    a[row][k] -= factor * a[col][k];
+                }
+                b[row] -= factor * b[col];
+            }
+        }
+    }
+
+    b
+}
+
+fn invert_3x3(m: [[f32; 3]; 3]) -> [[f32; 3]; 3] {
+    let det = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+    let inv_det = 1.0 / det;
+
+    [
+        [
+            (m[1][1] * m[2][2] - m[1][2] * m[2][1]) * inv_det,
+            (m[0][2] * m[2][1] - m[0][1] * m[2][2]) * inv_det,
+            (m[0][1] * m[1][2] - m[0][2] * m[1][1]) * inv_det,
+        ],
+        [
+            (m[1][2] * m[2][0] - m[1][0] * m[2][2]) * inv_det,
+            (m[0][0] * m[2][2] - m[0][2] * m[2][0]) * inv_det,
+            (m[0][2] * m[1][0] - m[0][0] * m[1][2]) * inv_det,
+        ],
+        [
+            (m[1][0] * m[2][1] - m[1][1] * m[2][0]) * inv_det,
+            (m[0][1] * m[2][0] - m[0][0] * m[2][1]) * inv_det,
+            (m[0][0] * m[1][1] - m[0][1] * m[1][0]) * inv_det,
+        ],
+    ]
+}
+
+fn apply_homography(h: &[[f32; 3]; 3], x: f32, y: f32) -> (f32, f32) {
+    let w = h[2][0] * x + h[2][1] * y + h[2][2];
+    let sx = (h[0][0] * x + h[0][1] * y + h[0][2]) / w;
+    let sy = (h[1][0] * x + h[1][1] * y + h[1][2]) / w;
+    (sx, sy)
+}
+
+/// Bilinear-sample an RGBA image at a fractional coordinate, clamping at borders.
+fn bilinear_sample(img: &RgbaImage, x: f32, y: f32) -> [u8; 4] {
+    let (w, h) = img.dimensions();
+    let x = x.clamp(0.0, (w - 1) as f32);
+    let y = y.clamp(0.0, (h - 1) as f32);
+
+    let x0 = x.floor() as u32;
+    let y0 = y.floor() as u32;
+    let x1 = (x0 + 1).min(w - 1);
+    let y1 = (y0 + 1).min(h - 1);
+
+    let fx = x - x0 as f32;
+    let fy = y - y0 as f32;
+
+    let p00 = img.get_pixel(x0, y0);
+    let p10 = img.get_pixel(x1, y0);
+    let p01 = img.get_pixel(x0, y1);
+    let p11 = img.get_pixel(x1, y1);
+
+    let mut out = [0u8; 4];
+    for c in 0..4 {
+        let top = p00[c] as f32 * (1.0 - fx) + p10[c] as f32 * fx;
+        let bottom = p01[c] as f32 * (1.0 - fx) + p11[c] as f32 * fx;
+        out[c] = (top * (1.0 - fy) + bottom * fy).round() as u8;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convex_hull_square() {
+        let points = vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0), (5.0, 5.0)];
+        let hull = convex_hull(&points);
+        assert_eq!(hull.len(), 4);
+    }
+
+    #[test]
+    fn test_simplify_to_quad_already_four() {
+        let hull = vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)];
+        let quad = simplify_to_quad(hull).unwrap();
+        assert_eq!(quad.len(), 4);
+    }
+
+    #[test]
+    fn test_homography_identity_mapping() {
+        let square = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)];
+        let h = solve_homography(square, square);
+        let (x, y) = apply_homography(&h, 4.0, 6.0);
+        assert!((x - 4.0).abs() < 0.01);
+        assert!((y - 6.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_order_corners_identifies_top_left() {
+        let pts = [(10.0, 10.0), (0.0, 0.0), (0.0, 10.0), (10.0, 0.0)];
+        let ordered = order_corners(pts);
+        assert_eq!(ordered[0], (0.0, 0.0));
+        assert_eq!(ordered[2], (10.0, 10.0));
+    }
+
+    #[test]
+    fn test_rectify_document_rejects_zero_dimensions() {
+        assert!(rectify_document(&[], 0, 100).is_err());
+        assert!(rectify_document(&[], 100, 0).is_err());
+    }
+}
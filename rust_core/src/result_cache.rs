@@ -0,0 +1,123 @@
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Content-addressed on-disk cache for expensive image ops.
+///
+/// The cache key is derived from the operation name, its parameters, and a
+/// hash of the input bytes, so the same (image, op, params) tuple always
+/// resolves to the same file regardless of when it was last computed.
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+static TEMP_SUFFIX_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A process- and call-unique temp path alongside `cache_path`, used so two
+/// threads racing to fill the same cache entry write to distinct files
+/// instead of one truncated/half-written file the other might read.
+fn unique_temp_path(cache_path: &Path) -> PathBuf {
+    let suffix = TEMP_SUFFIX_COUNTER.fetch_add(1, Ordering::Relaxed);
+    cache_path.with_extension(format!("tmp.{}.{}", std::process::id(), suffix))
+}
+
+/// Compute a stable cache key for `op_name` with the given `params` over `image_bytes`.
+pub fn cache_key(op_name: &str, image_bytes: &[u8], params: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(op_name.as_bytes());
+    hasher.update(b"|");
+    hasher.update(params.as_bytes());
+    hasher.update(b"|");
+    hasher.update(image_bytes);
+    to_hex(&hasher.finalize())
+}
+
+/// Return the cached PNG for `(op_name, image_bytes, params)` under `cache_dir`
+/// if present; otherwise run `compute`, cache its result, and return it.
+/// With `cache_dir` set to `None`, caching is skipped entirely.
+pub fn get_or_compute<F>(
+    cache_dir: Option<&str>,
+    op_name: &str,
+    image_bytes: &[u8],
+    params: &str,
+    compute: F,
+) -> PyResult<Vec<u8>>
+where
+    F: FnOnce() -> PyResult<Vec<u8>>,
+{
+    let Some(dir) = cache_dir else {
+        return compute();
+    };
+
+    let dir_path = PathBuf::from(dir);
+    fs::create_dir_all(&dir_path)
+        .map_err(|e| PyValueError::new_err(format!("Failed to create cache dir: {}", e)))?;
+
+    let key = cache_key(op_name, image_bytes, params);
+    let cache_path = dir_path.join(format!("{}.png", key));
+
+    if cache_path.exists() {
+        return fs::read(&cache_path)
+            .map_err(|e| PyValueError::new_err(format!("Failed to read cache entry: {}", e)));
+    }
+
+    let result = compute()?;
+
+    // Write to a unique temp file and rename into place so a concurrent
+    // reader never observes a partially-written cache entry.
+    let tmp_path = unique_temp_path(&cache_path);
+    fs::write(&tmp_path, &result)
+        .map_err(|e| PyValueError::new_err(format!("Failed to write cache entry: {}", e)))?;
+    fs::rename(&tmp_path, &cache_path)
+        .map_err(|e| PyValueError::new_err(format!("Failed to finalize cache entry: {}", e)))?;
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_key_stable_for_same_input() {
+        let a = cache_key("quantize_colors", b"image-bytes", "k=8,max_iter=10");
+        let b = cache_key("quantize_colors", b"image-bytes", "k=8,max_iter=10");
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_cache_key_differs_by_op_or_params() {
+        let base = cache_key("quantize_colors", b"image-bytes", "k=8,max_iter=10");
+        let different_op = cache_key("segment_image", b"image-bytes", "k=8,max_iter=10");
+        let different_params = cache_key("quantize_colors", b"image-bytes", "k=4,max_iter=10");
+        assert_ne!(base, different_op);
+        assert_ne!(base, different_params);
+    }
+
+    #[test]
+    fn test_get_or_compute_writes_via_temp_file_and_leaves_no_temp_behind() {
+        use rand::Rng;
+        let suffix: u64 = rand::thread_rng().gen();
+        let dir = std::env::temp_dir().join(format!("result_cache_test_{}", suffix));
+
+        let result = get_or_compute(Some(dir.to_str().unwrap()), "op", b"bytes", "params", || Ok(vec![1, 2, 3]));
+        assert_eq!(result.unwrap(), vec![1, 2, 3]);
+
+        let entries: Vec<_> = fs::read_dir(&dir).unwrap().map(|e| e.unwrap().file_name()).collect();
+        assert_eq!(entries.len(), 1, "only the final cache entry should remain, no leftover temp file");
+        assert!(entries[0].to_str().unwrap().ends_with(".png"));
+
+        // A second call with the same key should hit the cache rather than
+        // recompute, and the result should still round-trip correctly.
+        let cached = get_or_compute(Some(dir.to_str().unwrap()), "op", b"bytes", "params", || {
+            panic!("should not recompute a cached entry")
+        });
+        assert_eq!(cached.unwrap(), vec![1, 2, 3]);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
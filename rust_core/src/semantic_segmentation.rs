@@ -9,61 +9,108 @@ use std::collections::HashMap;
 /// Simple threshold-based segmentation (stub for future ML model)
 pub fn segment_image(
     image_bytes: &[u8],
-    num_regions: usize
+    num_regions: usize,
+    ignore_transparent: bool,
+    alpha_threshold: u8
 ) -> PyResult<Vec<u8>> {
     let img = image::load_from_memory(image_bytes)
         .map_err(|e| PyRuntimeError::new_err(format!("Failed to load image: {}", e)))?;
-    
+
     let (width, height) = img.dimensions();
     let rgb = img.to_rgb8();
-    
+
+    let mask: Option<Vec<bool>> = if ignore_transparent {
+        let rgba = img.to_rgba8();
+        Some(rgba.pixels().map(|p| p[3] >= alpha_threshold).collect())
+    } else {
+        None
+    };
+
     // K-means-based region segmentation
-    let segments = kmeans_segmentation(&rgb, num_regions);
-    
+    let segments = kmeans_segmentation(&rgb, num_regions, mask.as_deref());
+
+    if let Some(mask) = mask {
+        // Preserve transparency: excluded pixels are forced to region 0 and
+        // stay transparent in the output mask.
+        let mut mask_alpha = Vec::with_capacity((width * height * 2) as usize);
+        for (&region_id, &included) in segments.iter().zip(&mask) {
+            let value = if included {
+                ((region_id as f32 / num_regions as f32) * 255.0) as u8
+            } else {
+                0
+            };
+            mask_alpha.push(value);
+            mask_alpha.push(if included { 255 } else { 0 });
+        }
+
+        let mask_img = ImageBuffer::<image::LumaA<u8>, _>::from_raw(width, height, mask_alpha)
+            .ok_or_else(|| PyRuntimeError::new_err("Failed to create mask image"))?;
+
+        let mut png_data = Vec::new();
+        DynamicImage::ImageLumaA8(mask_img)
+            .write_to(&mut std::io::Cursor::new(&mut png_data), image::ImageFormat::Png)
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+
+        return Ok(png_data);
+    }
+
     // Create segmentation mask (each pixel assigned to region ID)
     let mask: Vec<u8> = segments.iter()
         .map(|&region_id| ((region_id as f32 / num_regions as f32) * 255.0) as u8)
         .collect();
-    
+
     // Convert to PNG
     let mask_img = ImageBuffer::<image::Luma<u8>, _>::from_raw(width, height, mask)
         .ok_or_else(|| PyRuntimeError::new_err("Failed to create mask image"))?;
-    
+
     let mut png_data = Vec::new();
     DynamicImage::ImageLuma8(mask_img)
         .write_to(&mut std::io::Cursor::new(&mut png_data), image::ImageFormat::Png)
         .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
-    
+
     Ok(png_data)
 }
 
-/// K-means segmentation in LAB color space
-fn kmeans_segmentation(img: &ImageBuffer<Rgb<u8>, Vec<u8>>, k: usize) -> Vec<usize> {
-    let (width, height) = img.dimensions();
+/// K-means segmentation in LAB color space.
+///
+/// `mask` marks pixels that participate in centroid seeding/updates; masked
+/// pixels (e.g. transparent background) still receive a nearest-region
+/// assignment but never pull a centroid toward them.
+fn kmeans_segmentation(img: &ImageBuffer<Rgb<u8>, Vec<u8>>, k: usize, mask: Option<&[bool]>) -> Vec<usize> {
     let pixels: Vec<(f32, f32, f32)> = img.pixels()
         .map(|p| {
             let lab = crate::color_lab::rgb_to_lab(p[0], p[1], p[2]);
             lab
         })
         .collect();
-    
+
+    let active_indices: Vec<usize> = match mask {
+        Some(m) => (0..pixels.len()).filter(|&i| m[i]).collect(),
+        None => (0..pixels.len()).collect(),
+    };
+    let seed_pool: Vec<(f32, f32, f32)> = if active_indices.is_empty() {
+        pixels.clone()
+    } else {
+        active_indices.iter().map(|&i| pixels[i]).collect()
+    };
+
     // Initialize centroids randomly
     let mut centroids: Vec<(f32, f32, f32)> = (0..k)
         .map(|i| {
-            let idx = (i * pixels.len() / k) % pixels.len();
-            pixels[idx]
+            let idx = (i * seed_pool.len() / k) % seed_pool.len();
+            seed_pool[idx]
         })
         .collect();
-    
+
     let mut assignments = vec![0usize; pixels.len()];
-    
+
     // K-means iterations
     for _iter in 0..10 {
         // Assign pixels to nearest centroid
         for (i, &(l, a, b)) in pixels.iter().enumerate() {
             let mut min_dist = f32::MAX;
             let mut best_k = 0;
-            
+
             for (k_idx, &(cl, ca, cb)) in centroids.iter().enumerate() {
                 let dist = crate::color_lab::color_distance_lab(l, a, b, cl, ca, cb);
                 if dist < min_dist {
@@ -71,22 +118,28 @@ fn kmeans_segmentation(img: &ImageBuffer<Rgb<u8>, Vec<u8>>, k: usize) -> Vec<usi
                     best_k = k_idx;
                 }
             }
-            
+
             assignments[i] = best_k;
         }
-        
+
         // Update centroids
         let mut sums = vec![(0.0f32, 0.0f32, 0.0f32); k];
         let mut counts = vec![0usize; k];
-        
+
         for (i, &(l, a, b)) in pixels.iter().enumerate() {
+            if let Some(m) = mask {
+                if !m[i] {
+                    continue;
+                }
+            }
+
             let k_idx = assignments[i];
             sums[k_idx].0 += l;
             sums[k_idx].1 += a;
             sums[k_idx].2 += b;
             counts[k_idx] += 1;
         }
-        
+
         for k_idx in 0..k {
             if counts[k_idx] > 0 {
                 centroids[k_idx] = (
@@ -97,7 +150,7 @@ fn kmeans_segmentation(img: &ImageBuffer<Rgb<u8>, Vec<u8>>, k: usize) -> Vec<usi
             }
         }
     }
-    
+
     assignments
 }
 
@@ -158,75 +211,117 @@ pub fn extract_layers(
 
 /// Detect salient objects using contrast and color analysis
 pub fn detect_salient_regions(
-    image_bytes: &[u8]
+    image_bytes: &[u8],
+    ignore_transparent: bool,
+    alpha_threshold: u8
 ) -> PyResult<Vec<u8>> {
     let img = image::load_from_memory(image_bytes)
         .map_err(|e| PyRuntimeError::new_err(format!("Failed to load image: {}", e)))?;
-    
+
     let (width, height) = img.dimensions();
     let rgb = img.to_rgb8();
-    
+
+    let mask: Option<Vec<bool>> = if ignore_transparent {
+        let rgba = img.to_rgba8();
+        Some(rgba.pixels().map(|p| p[3] >= alpha_threshold).collect())
+    } else {
+        None
+    };
+
     // Compute saliency map (sequential due to shared reference)
     let mut saliency = vec![0u8; (width * height) as usize];
-    
+
     for y in 0..height {
         for x in 0..width {
             let idx = (y * width + x) as usize;
-            saliency[idx] = compute_local_saliency(&rgb, x, y, width, height);
+            if let Some(m) = &mask {
+                if !m[idx] {
+                    saliency[idx] = 0;
+                    continue;
+                }
+            }
+            saliency[idx] = compute_local_saliency(&rgb, x, y, width, height, mask.as_deref());
         }
     }
-    
+
+    if let Some(mask) = mask {
+        let mut saliency_alpha = Vec::with_capacity((width * height * 2) as usize);
+        for (&value, &included) in saliency.iter().zip(&mask) {
+            saliency_alpha.push(value);
+            saliency_alpha.push(if included { 255 } else { 0 });
+        }
+
+        let saliency_img = ImageBuffer::<image::LumaA<u8>, _>::from_raw(width, height, saliency_alpha)
+            .ok_or_else(|| PyRuntimeError::new_err("Failed to create saliency map"))?;
+
+        let mut png_data = Vec::new();
+        DynamicImage::ImageLumaA8(saliency_img)
+            .write_to(&mut std::io::Cursor::new(&mut png_data), image::ImageFormat::Png)
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+
+        return Ok(png_data);
+    }
+
     // Convert to PNG
     let saliency_img = ImageBuffer::<image::Luma<u8>, _>::from_raw(width, height, saliency)
         .ok_or_else(|| PyRuntimeError::new_err("Failed to create saliency map"))?;
-    
+
     let mut png_data = Vec::new();
     DynamicImage::ImageLuma8(saliency_img)
         .write_to(&mut std::io::Cursor::new(&mut png_data), image::ImageFormat::Png)
         .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
-    
+
     Ok(png_data)
 }
 
-/// Compute local saliency using color and luminance contrast
+/// Compute local saliency using color and luminance contrast. Neighbors
+/// excluded by `mask` (e.g. transparent background) are skipped so they
+/// don't contribute spurious contrast around a sprite's border.
 fn compute_local_saliency(
     img: &ImageBuffer<Rgb<u8>, Vec<u8>>,
     x: u32,
     y: u32,
     width: u32,
-    height: u32
+    height: u32,
+    mask: Option<&[bool]>
 ) -> u8 {
     let center = img.get_pixel(x, y);
     let (cl, ca, cb) = crate::color_lab::rgb_to_lab(center[0], center[1], center[2]);
-    
+
     let window_size = 5;
     let mut contrast_sum = 0.0f32;
     let mut count = 0;
-    
+
     for dy in -(window_size as i32)..=(window_size as i32) {
         for dx in -(window_size as i32)..=(window_size as i32) {
             let nx = (x as i32 + dx).max(0).min(width as i32 - 1) as u32;
             let ny = (y as i32 + dy).max(0).min(height as i32 - 1) as u32;
-            
+
             if nx == x && ny == y {
                 continue;
             }
-            
+
+            if let Some(m) = mask {
+                if !m[(ny * width + nx) as usize] {
+                    continue;
+                }
+            }
+
             let neighbor = img.get_pixel(nx, ny);
             let (nl, na, nb) = crate::color_lab::rgb_to_lab(neighbor[0], neighbor[1], neighbor[2]);
-            
+
             let dist = crate::color_lab::color_distance_lab(cl, ca, cb, nl, na, nb);
             contrast_sum += dist;
             count += 1;
         }
     }
-    
+
     let saliency = if count > 0 {
         (contrast_sum / count as f32).min(255.0)
     } else {
         0.0
     };
-    
+
     saliency as u8
 }
 
@@ -238,7 +333,35 @@ mod tests {
     fn test_segmentation() {
         // Basic smoke test
         let img = image::RgbImage::new(100, 100);
-        let segments = kmeans_segmentation(&img, 5);
+        let segments = kmeans_segmentation(&img, 5, None);
         assert_eq!(segments.len(), 100 * 100);
     }
+
+    #[test]
+    fn test_kmeans_segmentation_mask_prevents_outlier_centroid_pull() {
+        // 10 red pixels, 10 green pixels, and 10 far-outlier blue pixels
+        // that are masked out. Left unmasked, the blue outliers would be
+        // assigned to one of the 2 regions and drag its centroid toward
+        // blue; masking them out should let red and green split cleanly.
+        let mut img = ImageBuffer::new(30, 1);
+        for x in 0..10 {
+            img.put_pixel(x, 0, Rgb([200, 20, 20]));
+        }
+        for x in 10..20 {
+            img.put_pixel(x, 0, Rgb([20, 200, 20]));
+        }
+        for x in 20..30 {
+            img.put_pixel(x, 0, Rgb([20, 20, 200]));
+        }
+
+        let mask: Vec<bool> = (0..30).map(|x| x < 20).collect();
+        let assignments = kmeans_segmentation(&img, 2, Some(&mask));
+
+        assert_eq!(assignments.len(), 30);
+        let red_region = assignments[0];
+        let green_region = assignments[10];
+        assert_ne!(red_region, green_region, "masking out the blue outliers should let red and green form separate clusters");
+        assert!(assignments[0..10].iter().all(|&a| a == red_region));
+        assert!(assignments[10..20].iter().all(|&a| a == green_region));
+    }
 }
@@ -1,33 +1,162 @@
-/// SIMD-accelerated operations for vectorization
-/// Uses x86_64 AVX2 instructions for 4-8x speedup on compatible CPUs
+/// SIMD-accelerated operations for vectorization.
+/// Uses x86_64 AVX2 (8-wide) on Intel/AMD and aarch64 NEON (4-wide) on
+/// Apple Silicon/ARM servers, falling back to scalar elsewhere. The
+/// `*_optimized` functions at the bottom of this module are the only
+/// entry points callers need — the architecture dispatch lives entirely
+/// inside them, so call sites never need their own `cfg`.
 
 #[cfg(target_arch = "x86_64")]
 use std::arch::x86_64::*;
 
-/// SIMD-accelerated RGB to LAB conversion
+#[cfg(target_arch = "aarch64")]
+use std::arch::aarch64::*;
+
+/// Fast base-2 exponential approximation (`2^x`) over `__m256` lanes.
+/// Accurate to within ~1e-3 relative error, which is plenty for the
+/// gamma/powf curves used by color conversion.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2", enable = "fma")]
+unsafe fn mm256_exp2_approx(x: __m256) -> __m256 {
+    let clipped = _mm256_max_ps(_mm256_min_ps(x, _mm256_set1_ps(126.0)), _mm256_set1_ps(-126.0));
+    let w = _mm256_floor_ps(clipped);
+    let z = _mm256_sub_ps(clipped, w);
+
+    // Degree-4 polynomial fit of 2^z on [0, 1).
+    let mut poly = _mm256_set1_ps(0.009618129107628477);
+    poly = _mm256_fmadd_ps(poly, z, _mm256_set1_ps(0.05550410866482158));
+    poly = _mm256_fmadd_ps(poly, z, _mm256_set1_ps(0.2402265069591007));
+    poly = _mm256_fmadd_ps(poly, z, _mm256_set1_ps(0.6931471805599453));
+    poly = _mm256_fmadd_ps(poly, z, _mm256_set1_ps(1.0));
+
+    let exponent = _mm256_cvtps_epi32(w);
+    let biased = _mm256_add_epi32(exponent, _mm256_set1_epi32(127));
+    let pow2w = _mm256_castsi256_ps(_mm256_slli_epi32(biased, 23));
+
+    _mm256_mul_ps(poly, pow2w)
+}
+
+/// Fast base-2 logarithm approximation (`log2(x)`) over `__m256` lanes via
+/// IEEE-754 bit manipulation plus a polynomial correction on the mantissa.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2", enable = "fma")]
+unsafe fn mm256_log2_approx(x: __m256) -> __m256 {
+    let bits = _mm256_castps_si256(x);
+    let exponent_bits = _mm256_srli_epi32(bits, 23);
+    let exponent = _mm256_sub_epi32(exponent_bits, _mm256_set1_epi32(127));
+    let exponent_f = _mm256_cvtepi32_ps(exponent);
+
+    let mantissa_bits = _mm256_or_si256(
+        _mm256_and_si256(bits, _mm256_set1_epi32(0x007F_FFFF)),
+        _mm256_set1_epi32(0x3F80_0000),
+    );
+    let mantissa = _mm256_castsi256_ps(mantissa_bits);
+
+    // Degree-4 polynomial fit of ln(mantissa) on [1, 2), scaled by log2(e)
+    // below to get log2(mantissa).
+    let mut poly = _mm256_set1_ps(-0.056570851);
+    poly = _mm256_fmadd_ps(poly, mantissa, _mm256_set1_ps(0.44717955));
+    poly = _mm256_fmadd_ps(poly, mantissa, _mm256_set1_ps(-1.4699568));
+    poly = _mm256_fmadd_ps(poly, mantissa, _mm256_set1_ps(2.8212026));
+    poly = _mm256_fmadd_ps(poly, mantissa, _mm256_set1_ps(-1.7417939));
+
+    _mm256_fmadd_ps(poly, _mm256_set1_ps(std::f32::consts::LOG2_E), exponent_f)
+}
+
+/// Vectorized `base.powf(exponent)` built from the log2/exp2 approximations
+/// above, since AVX2 has no native `powf` instruction.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2", enable = "fma")]
+unsafe fn mm256_powf_approx(base: __m256, exponent: f32) -> __m256 {
+    let log2_base = mm256_log2_approx(base);
+    let scaled = _mm256_mul_ps(log2_base, _mm256_set1_ps(exponent));
+    mm256_exp2_approx(scaled)
+}
+
+/// Vectorized sRGB gamma-to-linear piecewise curve (see `color_lab::rgb_to_lab`).
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2", enable = "fma")]
+unsafe fn mm256_gamma_to_linear(c: __m256) -> __m256 {
+    let linear_low = _mm256_div_ps(c, _mm256_set1_ps(12.92));
+
+    let shifted = _mm256_div_ps(_mm256_add_ps(c, _mm256_set1_ps(0.055)), _mm256_set1_ps(1.055));
+    let linear_high = mm256_powf_approx(shifted, 2.4);
+
+    let mask = _mm256_cmp_ps(c, _mm256_set1_ps(0.04045), _CMP_LE_OQ);
+    _mm256_blendv_ps(linear_high, linear_low, mask)
+}
+
+/// Widen 8 contiguous `u8` samples into an `__m256` of `[0, 255]` floats.
 #[cfg(target_arch = "x86_64")]
 #[target_feature(enable = "avx2")]
+unsafe fn mm256_load_u8x8_as_ps(data: &[u8]) -> __m256 {
+    let bytes = _mm_loadl_epi64(data.as_ptr() as *const __m128i);
+    let widened = _mm256_cvtepu8_epi32(bytes);
+    _mm256_cvtepi32_ps(widened)
+}
+
+/// SIMD-accelerated RGB to LAB conversion.
+///
+/// Gamma-decoding and the linear-RGB-to-XYZ matrix multiply run 8 pixels at
+/// a time as `_mm256` FMAs; the final (non-linear, cbrt-based) XYZ-to-LAB
+/// step is cheap enough per-pixel that it's left scalar.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2", enable = "fma")]
 pub unsafe fn rgb_to_lab_simd(rgb_data: &[u8], lab_output: &mut [(f32, f32, f32)]) {
-    // Process 8 pixels at a time with AVX2
-    let chunks = rgb_data.len() / 24; // 8 pixels * 3 channels
-    
+    let num_pixels = lab_output.len();
+    let chunks = num_pixels / 8;
+
     for i in 0..chunks {
-        let offset = i * 24;
-        
-        // Load 8 RGB pixels (24 bytes)
-        // Note: This is a simplified version - production code needs proper alignment
+        let offset = i * 8 * 3;
+
+        // Deinterleave 8 RGB24 pixels into separate R/G/B f32x8 lanes.
+        let mut r_arr = [0f32; 8];
+        let mut g_arr = [0f32; 8];
+        let mut b_arr = [0f32; 8];
+        for j in 0..8 {
+            r_arr[j] = rgb_data[offset + j * 3] as f32;
+            g_arr[j] = rgb_data[offset + j * 3 + 1] as f32;
+            b_arr[j] = rgb_data[offset + j * 3 + 2] as f32;
+        }
+
+        let scale = _mm256_set1_ps(1.0 / 255.0);
+        let r = _mm256_mul_ps(_mm256_loadu_ps(r_arr.as_ptr()), scale);
+        let g = _mm256_mul_ps(_mm256_loadu_ps(g_arr.as_ptr()), scale);
+        let b = _mm256_mul_ps(_mm256_loadu_ps(b_arr.as_ptr()), scale);
+
+        let r_lin = mm256_gamma_to_linear(r);
+        let g_lin = mm256_gamma_to_linear(g);
+        let b_lin = mm256_gamma_to_linear(b);
+
+        // Linear RGB -> XYZ (D65), as fused multiply-adds.
+        let x = _mm256_fmadd_ps(
+            b_lin, _mm256_set1_ps(0.1804375),
+            _mm256_fmadd_ps(g_lin, _mm256_set1_ps(0.3575761), _mm256_mul_ps(r_lin, _mm256_set1_ps(0.4124564))),
+        );
+        let y = _mm256_fmadd_ps(
+            b_lin, _mm256_set1_ps(0.0721750),
+            _mm256_fmadd_ps(g_lin, _mm256_set1_ps(0.7151522), _mm256_mul_ps(r_lin, _mm256_set1_ps(0.2126729))),
+        );
+        let z = _mm256_fmadd_ps(
+            b_lin, _mm256_set1_ps(0.9503041),
+            _mm256_fmadd_ps(g_lin, _mm256_set1_ps(0.1191920), _mm256_mul_ps(r_lin, _mm256_set1_ps(0.0193339))),
+        );
+
+        let hundred = _mm256_set1_ps(100.0);
+        let mut x_arr = [0f32; 8];
+        let mut y_arr = [0f32; 8];
+        let mut z_arr = [0f32; 8];
+        _mm256_storeu_ps(x_arr.as_mut_ptr(), _mm256_mul_ps(x, hundred));
+        _mm256_storeu_ps(y_arr.as_mut_ptr(), _mm256_mul_ps(y, hundred));
+        _mm256_storeu_ps(z_arr.as_mut_ptr(), _mm256_mul_ps(z, hundred));
+
         for j in 0..8 {
-            let r = rgb_data[offset + j * 3];
-            let g = rgb_data[offset + j * 3 + 1];
-            let b = rgb_data[offset + j * 3 + 2];
-            
-            lab_output[i * 8 + j] = crate::color_lab::rgb_to_lab(r, g, b);
+            lab_output[i * 8 + j] = crate::color_lab::xyz_to_lab(x_arr[j], y_arr[j], z_arr[j]);
         }
     }
-    
-    // Handle remaining pixels
+
+    // Scalar remainder.
     let remainder_start = chunks * 8;
-    for i in remainder_start..lab_output.len() {
+    for i in remainder_start..num_pixels {
         let r = rgb_data[i * 3];
         let g = rgb_data[i * 3 + 1];
         let b = rgb_data[i * 3 + 2];
@@ -35,25 +164,60 @@ pub unsafe fn rgb_to_lab_simd(rgb_data: &[u8], lab_output: &mut [(f32, f32, f32)
     }
 }
 
-/// SIMD-accelerated Sobel gradient computation
+/// SIMD-accelerated Sobel gradient computation, 8 output pixels per lane.
 #[cfg(target_arch = "x86_64")]
-#[target_feature(enable = "avx2")]
+#[target_feature(enable = "avx2", enable = "fma")]
 pub unsafe fn sobel_gradient_simd(
     image: &[u8],
     width: usize,
     height: usize,
     output: &mut [f32]
 ) {
-    // Sobel kernels
-    let sobel_x = [-1i32, 0, 1, -2, 0, 2, -1, 0, 1];
-    let sobel_y = [-1i32, -2, -1, 0, 0, 0, 1, 2, 1];
-    
-    for y in 1..height-1 {
-        for x in 1..width-1 {
+    let two = _mm256_set1_ps(2.0);
+
+    for y in 1..height - 1 {
+        let row_above = &image[(y - 1) * width..(y - 1) * width + width];
+        let row_mid = &image[y * width..y * width + width];
+        let row_below = &image[(y + 1) * width..(y + 1) * width + width];
+
+        let mut x = 1;
+        while x + 8 <= width - 1 {
+            let tl = mm256_load_u8x8_as_ps(&row_above[x - 1..]);
+            let tm = mm256_load_u8x8_as_ps(&row_above[x..]);
+            let tr = mm256_load_u8x8_as_ps(&row_above[x + 1..]);
+            let ml = mm256_load_u8x8_as_ps(&row_mid[x - 1..]);
+            let mr = mm256_load_u8x8_as_ps(&row_mid[x + 1..]);
+            let bl = mm256_load_u8x8_as_ps(&row_below[x - 1..]);
+            let bm = mm256_load_u8x8_as_ps(&row_below[x..]);
+            let br = mm256_load_u8x8_as_ps(&row_below[x + 1..]);
+
+            // gx = (tr - tl) + 2*(mr - ml) + (br - bl)
+            let gx = _mm256_add_ps(
+                _mm256_sub_ps(tr, tl),
+                _mm256_fmadd_ps(two, _mm256_sub_ps(mr, ml), _mm256_sub_ps(br, bl)),
+            );
+
+            // gy = (bl + 2*bm + br) - (tl + 2*tm + tr)
+            let gy = _mm256_add_ps(
+                _mm256_sub_ps(_mm256_add_ps(bl, br), _mm256_add_ps(tl, tr)),
+                _mm256_mul_ps(two, _mm256_sub_ps(bm, tm)),
+            );
+
+            let magnitude = _mm256_sqrt_ps(_mm256_fmadd_ps(gx, gx, _mm256_mul_ps(gy, gy)));
+
+            let mut out_arr = [0f32; 8];
+            _mm256_storeu_ps(out_arr.as_mut_ptr(), magnitude);
+            output[y * width + x..y * width + x + 8].copy_from_slice(&out_arr);
+
+            x += 8;
+        }
+
+        // Scalar remainder for the last partial lane.
+        let sobel_x = [-1i32, 0, 1, -2, 0, 2, -1, 0, 1];
+        let sobel_y = [-1i32, -2, -1, 0, 0, 0, 1, 2, 1];
+        while x < width - 1 {
             let mut gx = 0i32;
             let mut gy = 0i32;
-            
-            // Apply 3x3 Sobel kernel
             for ky in 0..3 {
                 for kx in 0..3 {
                     let px = image[(y + ky - 1) * width + (x + kx - 1)] as i32;
@@ -61,68 +225,330 @@ pub unsafe fn sobel_gradient_simd(
                     gy += px * sobel_y[ky * 3 + kx];
                 }
             }
-            
             output[y * width + x] = ((gx * gx + gy * gy) as f32).sqrt();
+            x += 1;
         }
     }
 }
 
-/// SIMD-accelerated color distance calculation
+/// SIMD-accelerated color distance calculation against a single centroid.
+///
+/// `l`, `a`, `b` are a struct-of-arrays LAB buffer (see
+/// `color_lab::kmeans_lab`); keeping each channel in its own contiguous
+/// slice lets the squared-difference and `_mm256_sqrt_ps` run fully
+/// parallel across 8 pixels instead of per-pixel scalar math.
 #[cfg(target_arch = "x86_64")]
-#[target_feature(enable = "avx2")]
+#[target_feature(enable = "avx2", enable = "fma")]
 pub unsafe fn color_distance_batch_simd(
-    colors: &[(f32, f32, f32)],
+    l: &[f32],
+    a: &[f32],
+    b: &[f32],
     centroid: (f32, f32, f32),
     distances: &mut [f32]
 ) {
     let (cl, ca, cb) = centroid;
-    
-    // Load centroid into SIMD registers
     let cl_vec = _mm256_set1_ps(cl);
     let ca_vec = _mm256_set1_ps(ca);
     let cb_vec = _mm256_set1_ps(cb);
-    
-    // Process 8 colors at a time
-    let chunks = colors.len() / 8;
-    
+
+    let n = l.len();
+    let chunks = n / 8;
+
     for i in 0..chunks {
         let offset = i * 8;
-        
-        // Simplified version - production needs proper memory layout
-        for j in 0..8 {
-            let (l, a, b) = colors[offset + j];
-            let dl = l - cl;
-            let da = a - ca;
-            let db = b - cb;
-            distances[offset + j] = (dl * dl + da * da + db * db).sqrt();
+        let lv = _mm256_loadu_ps(l[offset..].as_ptr());
+        let av = _mm256_loadu_ps(a[offset..].as_ptr());
+        let bv = _mm256_loadu_ps(b[offset..].as_ptr());
+
+        let dl = _mm256_sub_ps(lv, cl_vec);
+        let da = _mm256_sub_ps(av, ca_vec);
+        let db = _mm256_sub_ps(bv, cb_vec);
+
+        let sq_sum = _mm256_fmadd_ps(db, db, _mm256_fmadd_ps(da, da, _mm256_mul_ps(dl, dl)));
+        let dist = _mm256_sqrt_ps(sq_sum);
+
+        _mm256_storeu_ps(distances[offset..].as_mut_ptr(), dist);
+    }
+
+    for i in (chunks * 8)..n {
+        distances[i] = crate::color_lab::color_distance_lab(l[i], a[i], b[i], cl, ca, cb);
+    }
+}
+
+// ---------------------------------------------------------------------
+// aarch64 NEON backend: the same three kernels as the AVX2 section above,
+// 4-wide instead of 8-wide (`float32x4_t`), using the same log2/exp2
+// polynomial trick for the gamma curve since NEON has no `powf` either.
+// ---------------------------------------------------------------------
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+unsafe fn neon_exp2_approx(x: float32x4_t) -> float32x4_t {
+    let clipped = vminq_f32(vmaxq_f32(x, vdupq_n_f32(-126.0)), vdupq_n_f32(126.0));
+    let w = vrndmq_f32(clipped);
+    let z = vsubq_f32(clipped, w);
+
+    let mut poly = vdupq_n_f32(0.009618129107628477);
+    poly = vfmaq_f32(vdupq_n_f32(0.05550410866482158), poly, z);
+    poly = vfmaq_f32(vdupq_n_f32(0.2402265069591007), poly, z);
+    poly = vfmaq_f32(vdupq_n_f32(0.6931471805599453), poly, z);
+    poly = vfmaq_f32(vdupq_n_f32(1.0), poly, z);
+
+    let exponent = vcvtq_s32_f32(w);
+    let biased = vaddq_s32(exponent, vdupq_n_s32(127));
+    let pow2w = vreinterpretq_f32_s32(vshlq_n_s32(biased, 23));
+
+    vmulq_f32(poly, pow2w)
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+unsafe fn neon_log2_approx(x: float32x4_t) -> float32x4_t {
+    let bits = vreinterpretq_s32_f32(x);
+    let exponent_bits = vshrq_n_u32(vreinterpretq_u32_s32(bits), 23);
+    let exponent = vsubq_s32(vreinterpretq_s32_u32(exponent_bits), vdupq_n_s32(127));
+    let exponent_f = vcvtq_f32_s32(exponent);
+
+    let mantissa_bits = vorrq_s32(
+        vandq_s32(bits, vdupq_n_s32(0x007F_FFFF)),
+        vdupq_n_s32(0x3F80_0000),
+    );
+    let mantissa = vreinterpretq_f32_s32(mantissa_bits);
+
+    let mut poly = vdupq_n_f32(-0.056570851);
+    poly = vfmaq_f32(vdupq_n_f32(0.44717955), poly, mantissa);
+    poly = vfmaq_f32(vdupq_n_f32(-1.4699568), poly, mantissa);
+    poly = vfmaq_f32(vdupq_n_f32(2.8212026), poly, mantissa);
+    poly = vfmaq_f32(vdupq_n_f32(-1.7417939), poly, mantissa);
+
+    // `poly` approximates ln(mantissa); scale by log2(e) for log2.
+    vfmaq_f32(exponent_f, poly, vdupq_n_f32(std::f32::consts::LOG2_E))
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+unsafe fn neon_powf_approx(base: float32x4_t, exponent: f32) -> float32x4_t {
+    let log2_base = neon_log2_approx(base);
+    let scaled = vmulq_f32(log2_base, vdupq_n_f32(exponent));
+    neon_exp2_approx(scaled)
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+unsafe fn neon_gamma_to_linear(c: float32x4_t) -> float32x4_t {
+    let linear_low = vdivq_f32(c, vdupq_n_f32(12.92));
+    let shifted = vdivq_f32(vaddq_f32(c, vdupq_n_f32(0.055)), vdupq_n_f32(1.055));
+    let linear_high = neon_powf_approx(shifted, 2.4);
+
+    let mask = vcleq_f32(c, vdupq_n_f32(0.04045));
+    vbslq_f32(mask, linear_low, linear_high)
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+unsafe fn neon_load_u8x4_as_f32(data: &[u8]) -> float32x4_t {
+    let widened: [u32; 4] = [data[0] as u32, data[1] as u32, data[2] as u32, data[3] as u32];
+    vcvtq_f32_u32(vld1q_u32(widened.as_ptr()))
+}
+
+/// NEON-accelerated RGB to LAB conversion, 4 pixels per lane.
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+pub unsafe fn rgb_to_lab_neon(rgb_data: &[u8], lab_output: &mut [(f32, f32, f32)]) {
+    let num_pixels = lab_output.len();
+    let chunks = num_pixels / 4;
+
+    for i in 0..chunks {
+        let offset = i * 4 * 3;
+
+        let mut r_arr = [0f32; 4];
+        let mut g_arr = [0f32; 4];
+        let mut b_arr = [0f32; 4];
+        for j in 0..4 {
+            r_arr[j] = rgb_data[offset + j * 3] as f32;
+            g_arr[j] = rgb_data[offset + j * 3 + 1] as f32;
+            b_arr[j] = rgb_data[offset + j * 3 + 2] as f32;
+        }
+
+        let scale = vdupq_n_f32(1.0 / 255.0);
+        let r = vmulq_f32(vld1q_f32(r_arr.as_ptr()), scale);
+        let g = vmulq_f32(vld1q_f32(g_arr.as_ptr()), scale);
+        let b = vmulq_f32(vld1q_f32(b_arr.as_ptr()), scale);
+
+        let r_lin = neon_gamma_to_linear(r);
+        let g_lin = neon_gamma_to_linear(g);
+        let b_lin = neon_gamma_to_linear(b);
+
+        let x = vfmaq_f32(vfmaq_f32(vmulq_f32(r_lin, vdupq_n_f32(0.4124564)), g_lin, vdupq_n_f32(0.3575761)), b_lin, vdupq_n_f32(0.1804375));
+        let y = vfmaq_f32(vfmaq_f32(vmulq_f32(r_lin, vdupq_n_f32(0.2126729)), g_lin, vdupq_n_f32(0.7151522)), b_lin, vdupq_n_f32(0.0721750));
+        let z = vfmaq_f32(vfmaq_f32(vmulq_f32(r_lin, vdupq_n_f32(0.0193339)), g_lin, vdupq_n_f32(0.1191920)), b_lin, vdupq_n_f32(0.9503041));
+
+        let hundred = vdupq_n_f32(100.0);
+        let mut x_arr = [0f32; 4];
+        let mut y_arr = [0f32; 4];
+        let mut z_arr = [0f32; 4];
+        vst1q_f32(x_arr.as_mut_ptr(), vmulq_f32(x, hundred));
+        vst1q_f32(y_arr.as_mut_ptr(), vmulq_f32(y, hundred));
+        vst1q_f32(z_arr.as_mut_ptr(), vmulq_f32(z, hundred));
+
+        for j in 0..4 {
+            lab_output[i * 4 + j] = crate::color_lab::xyz_to_lab(x_arr[j], y_arr[j], z_arr[j]);
         }
     }
-    
-    // Handle remainder
-    for i in (chunks * 8)..colors.len() {
-        let (l, a, b) = colors[i];
-        distances[i] = crate::color_lab::color_distance_lab(l, a, b, cl, ca, cb);
+
+    let remainder_start = chunks * 4;
+    for i in remainder_start..num_pixels {
+        let r = rgb_data[i * 3];
+        let g = rgb_data[i * 3 + 1];
+        let b = rgb_data[i * 3 + 2];
+        lab_output[i] = crate::color_lab::rgb_to_lab(r, g, b);
     }
 }
 
-/// Check if CPU supports required SIMD instructions
+/// NEON-accelerated Sobel gradient computation, 4 output pixels per lane.
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+pub unsafe fn sobel_gradient_neon(
+    image: &[u8],
+    width: usize,
+    height: usize,
+    output: &mut [f32]
+) {
+    let two = vdupq_n_f32(2.0);
+
+    for y in 1..height - 1 {
+        let row_above = &image[(y - 1) * width..(y - 1) * width + width];
+        let row_mid = &image[y * width..y * width + width];
+        let row_below = &image[(y + 1) * width..(y + 1) * width + width];
+
+        let mut x = 1;
+        while x + 4 <= width - 1 {
+            let tl = neon_load_u8x4_as_f32(&row_above[x - 1..]);
+            let tm = neon_load_u8x4_as_f32(&row_above[x..]);
+            let tr = neon_load_u8x4_as_f32(&row_above[x + 1..]);
+            let ml = neon_load_u8x4_as_f32(&row_mid[x - 1..]);
+            let mr = neon_load_u8x4_as_f32(&row_mid[x + 1..]);
+            let bl = neon_load_u8x4_as_f32(&row_below[x - 1..]);
+            let bm = neon_load_u8x4_as_f32(&row_below[x..]);
+            let br = neon_load_u8x4_as_f32(&row_below[x + 1..]);
+
+            let gx = vaddq_f32(
+                vsubq_f32(tr, tl),
+                vfmaq_f32(vsubq_f32(br, bl), two, vsubq_f32(mr, ml)),
+            );
+
+            let gy = vaddq_f32(
+                vsubq_f32(vaddq_f32(bl, br), vaddq_f32(tl, tr)),
+                vmulq_f32(two, vsubq_f32(bm, tm)),
+            );
+
+            let magnitude = vsqrtq_f32(vfmaq_f32(vmulq_f32(gx, gx), gy, gy));
+
+            let mut out_arr = [0f32; 4];
+            vst1q_f32(out_arr.as_mut_ptr(), magnitude);
+            output[y * width + x..y * width + x + 4].copy_from_slice(&out_arr);
+
+            x += 4;
+        }
+
+        let sobel_x = [-1i32, 0, 1, -2, 0, 2, -1, 0, 1];
+        let sobel_y = [-1i32, -2, -1, 0, 0, 0, 1, 2, 1];
+        while x < width - 1 {
+            let mut gx = 0i32;
+            let mut gy = 0i32;
+            for ky in 0..3 {
+                for kx in 0..3 {
+                    let px = image[(y + ky - 1) * width + (x + kx - 1)] as i32;
+                    gx += px * sobel_x[ky * 3 + kx];
+                    gy += px * sobel_y[ky * 3 + kx];
+                }
+            }
+            output[y * width + x] = ((gx * gx + gy * gy) as f32).sqrt();
+            x += 1;
+        }
+    }
+}
+
+/// NEON-accelerated color distance calculation against a single centroid,
+/// over the same struct-of-arrays LAB buffer as `color_distance_batch_simd`.
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+pub unsafe fn color_distance_batch_neon(
+    l: &[f32],
+    a: &[f32],
+    b: &[f32],
+    centroid: (f32, f32, f32),
+    distances: &mut [f32]
+) {
+    let (cl, ca, cb) = centroid;
+    let cl_vec = vdupq_n_f32(cl);
+    let ca_vec = vdupq_n_f32(ca);
+    let cb_vec = vdupq_n_f32(cb);
+
+    let n = l.len();
+    let chunks = n / 4;
+
+    for i in 0..chunks {
+        let offset = i * 4;
+        let lv = vld1q_f32(l[offset..].as_ptr());
+        let av = vld1q_f32(a[offset..].as_ptr());
+        let bv = vld1q_f32(b[offset..].as_ptr());
+
+        let dl = vsubq_f32(lv, cl_vec);
+        let da = vsubq_f32(av, ca_vec);
+        let db = vsubq_f32(bv, cb_vec);
+
+        let sq_sum = vfmaq_f32(vfmaq_f32(vmulq_f32(dl, dl), da, da), db, db);
+        let dist = vsqrtq_f32(sq_sum);
+
+        vst1q_f32(distances[offset..].as_mut_ptr(), dist);
+    }
+
+    for i in (chunks * 4)..n {
+        distances[i] = crate::color_lab::color_distance_lab(l[i], a[i], b[i], cl, ca, cb);
+    }
+}
+
+/// Check if the current CPU supports this module's SIMD backend: AVX2+FMA
+/// on x86_64, always-available NEON on aarch64, or neither elsewhere.
 pub fn has_simd_support() -> bool {
     #[cfg(target_arch = "x86_64")]
     {
-        is_x86_feature_detected!("avx2")
+        is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma")
     }
-    
-    #[cfg(not(target_arch = "x86_64"))]
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        // NEON is mandatory baseline on aarch64, no runtime check needed.
+        true
+    }
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
     {
         false
     }
 }
 
-/// Dispatch to SIMD or fallback implementation
-pub fn rgb_to_lab_optimized(rgb_data: &[u8]) -> Vec<(f32, f32, f32)> {
+/// Dispatch to SIMD or fallback implementation.
+///
+/// `profile` lets callers convert non-sRGB-tagged images (Adobe RGB,
+/// Display P3, a parsed camera ICC profile) correctly. The AVX2 fast path
+/// hardcodes the sRGB gamma/matrix, so a `Some` profile always takes the
+/// scalar `rgb_to_lab_with_profile` route instead.
+pub fn rgb_to_lab_optimized(rgb_data: &[u8], profile: Option<&crate::color_lab::ColorTransform>) -> Vec<(f32, f32, f32)> {
     let num_pixels = rgb_data.len() / 3;
     let mut lab_output = vec![(0.0f32, 0.0f32, 0.0f32); num_pixels];
-    
+
+    if let Some(transform) = profile {
+        for i in 0..num_pixels {
+            let r = rgb_data[i * 3];
+            let g = rgb_data[i * 3 + 1];
+            let b = rgb_data[i * 3 + 2];
+            lab_output[i] = crate::color_lab::rgb_to_lab_with_profile(r, g, b, transform);
+        }
+        return lab_output;
+    }
+
     #[cfg(target_arch = "x86_64")]
     {
         if has_simd_support() {
@@ -132,7 +558,17 @@ pub fn rgb_to_lab_optimized(rgb_data: &[u8]) -> Vec<(f32, f32, f32)> {
             return lab_output;
         }
     }
-    
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        if has_simd_support() {
+            unsafe {
+                rgb_to_lab_neon(rgb_data, &mut lab_output);
+            }
+            return lab_output;
+        }
+    }
+
     // Fallback: scalar processing
     for i in 0..num_pixels {
         let r = rgb_data[i * 3];
@@ -140,23 +576,150 @@ pub fn rgb_to_lab_optimized(rgb_data: &[u8]) -> Vec<(f32, f32, f32)> {
         let b = rgb_data[i * 3 + 2];
         lab_output[i] = crate::color_lab::rgb_to_lab(r, g, b);
     }
-    
+
     lab_output
 }
 
+/// Compute LAB distances from a struct-of-arrays buffer to one centroid,
+/// dispatching to the AVX2 or NEON batch kernel when available.
+pub fn color_distance_batch_optimized(
+    l: &[f32],
+    a: &[f32],
+    b: &[f32],
+    centroid: (f32, f32, f32),
+    distances: &mut [f32],
+) {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if has_simd_support() {
+            unsafe {
+                color_distance_batch_simd(l, a, b, centroid, distances);
+            }
+            return;
+        }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        if has_simd_support() {
+            unsafe {
+                color_distance_batch_neon(l, a, b, centroid, distances);
+            }
+            return;
+        }
+    }
+
+    let (cl, ca, cb) = centroid;
+    for i in 0..l.len() {
+        distances[i] = crate::color_lab::color_distance_lab(l[i], a[i], b[i], cl, ca, cb);
+    }
+}
+
+/// Compute Sobel gradient magnitudes over a grayscale buffer, dispatching
+/// to the AVX2 or NEON batch kernel when available.
+pub fn sobel_gradient_optimized(image: &[u8], width: usize, height: usize, output: &mut [f32]) {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if has_simd_support() {
+            unsafe {
+                sobel_gradient_simd(image, width, height, output);
+            }
+            return;
+        }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        if has_simd_support() {
+            unsafe {
+                sobel_gradient_neon(image, width, height, output);
+            }
+            return;
+        }
+    }
+
+    let sobel_x = [-1i32, 0, 1, -2, 0, 2, -1, 0, 1];
+    let sobel_y = [-1i32, -2, -1, 0, 0, 0, 1, 2, 1];
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            let mut gx = 0i32;
+            let mut gy = 0i32;
+            for ky in 0..3 {
+                for kx in 0..3 {
+                    let px = image[(y + ky - 1) * width + (x + kx - 1)] as i32;
+                    gx += px * sobel_x[ky * 3 + kx];
+                    gy += px * sobel_y[ky * 3 + kx];
+                }
+            }
+            output[y * width + x] = ((gx * gx + gy * gy) as f32).sqrt();
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    
+
     #[test]
     fn test_simd_availability() {
         println!("SIMD support: {}", has_simd_support());
     }
-    
+
     #[test]
     fn test_rgb_to_lab_optimized() {
         let rgb = vec![255u8, 0, 0, 0, 255, 0, 0, 0, 255];
-        let lab = rgb_to_lab_optimized(&rgb);
+        let lab = rgb_to_lab_optimized(&rgb, None);
         assert_eq!(lab.len(), 3);
     }
+
+    #[test]
+    fn test_rgb_to_lab_optimized_with_profile() {
+        let rgb = vec![255u8, 255, 255, 0, 0, 0];
+        let transform = crate::color_lab::ColorTransform::for_working_space(crate::color_lab::WorkingSpace::AdobeRgb);
+        let lab = rgb_to_lab_optimized(&rgb, Some(&transform));
+        assert_eq!(lab.len(), 2);
+        assert!((lab[0].0 - 100.0).abs() < 0.5); // White should still be L ≈ 100
+        assert!((lab[1].0 - 0.0).abs() < 0.5);   // Black should still be L ≈ 0
+    }
+
+    #[test]
+    fn test_color_distance_batch_optimized() {
+        let l = vec![50.0f32; 16];
+        let a = vec![10.0f32; 16];
+        let b = vec![-10.0f32; 16];
+        let mut distances = vec![0.0f32; 16];
+        color_distance_batch_optimized(&l, &a, &b, (50.0, 10.0, -10.0), &mut distances);
+        for d in distances {
+            assert!(d < 0.001);
+        }
+    }
+
+    #[test]
+    fn test_sobel_gradient_optimized_flat_image_has_no_edges() {
+        let width = 12;
+        let height = 12;
+        let image = vec![128u8; width * height];
+        let mut output = vec![0f32; width * height];
+        sobel_gradient_optimized(&image, width, height, &mut output);
+        for &v in &output {
+            assert!(v < 0.001);
+        }
+    }
+
+    #[test]
+    fn test_sobel_gradient_optimized_detects_vertical_edge() {
+        let width = 12;
+        let height = 12;
+        let mut image = vec![0u8; width * height];
+        for y in 0..height {
+            for x in width / 2..width {
+                image[y * width + x] = 255;
+            }
+        }
+        let mut output = vec![0f32; width * height];
+        sobel_gradient_optimized(&image, width, height, &mut output);
+        let mid_row = height / 2;
+        let at_edge = output[mid_row * width + width / 2];
+        assert!(at_edge > 100.0);
+    }
 }